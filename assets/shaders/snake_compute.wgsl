@@ -1,8 +1,9 @@
+#import snake_mesh::sdf::{SnakeSdf, follow_body, sdf, sdf_color, sdf_gradient}
+
 struct PolygonizationInfo {
     grid_size: vec3<f32>,
     grid_origin: vec3<f32>, // bottom left corner
-    sphere_center: vec3<f32>,
-    sphere_radius: f32,
+    snake: SnakeSdf,
 }
 
 struct DrawIndexedIndirect {
@@ -24,17 +25,46 @@ struct CellInfo {
 @group(0) @binding(3) var<storage, read_write> cells: array<CellInfo>;
 @group(0) @binding(4) var<storage, read_write> atomics: array<atomic<u32>, 2>;
 @group(0) @binding(5) var<storage, read_write> indirect: DrawIndexedIndirect;
+@group(0) @binding(6) var<uniform> previous_polygonization_info: PolygonizationInfo;
+// one per cell, written by relax_vertices and read by apply_relaxed_vertices
+@group(0) @binding(7) var<storage, read_write> relaxed_positions: array<vec4<f32>>;
+
+// position: vec3<f32>, previous_position: vec3<f32>, normal: vec3<f32>, color: vec4<f32>
+const VERTEX_STRIDE: u32 = 13u;
+// how far a vertex moves toward the average of its neighbors in one relaxation iteration
+const RELAXATION_FACTOR: f32 = 0.5;
+// vbo_index of a cell whose vertex didn't fit into the vbo, quads around it are dropped
+const NO_VERTEX: u32 = 0xffffffffu;
 
 fn flat_invocation_id(invocation_id: vec3<u32>, invocations_number: vec3<u32>) -> u32 {
     return invocation_id.x + invocation_id.y * invocations_number.x + invocation_id.z * invocations_number.x * invocations_number.y;
 }
 
 // because vec3f has 16 bytes alighnment
-fn set_vertex(index: u32, value: vec3<f32>) {
-    let offset = index * 3;
-    vbo[offset] = value.x;
-    vbo[offset + 1] = value.y;
-    vbo[offset + 2] = value.z;
+fn set_vertex(index: u32, position: vec3<f32>, previous_position: vec3<f32>, normal: vec3<f32>, color: vec4<f32>) {
+    let offset = index * VERTEX_STRIDE;
+    vbo[offset] = position.x;
+    vbo[offset + 1] = position.y;
+    vbo[offset + 2] = position.z;
+    vbo[offset + 3] = previous_position.x;
+    vbo[offset + 4] = previous_position.y;
+    vbo[offset + 5] = previous_position.z;
+    vbo[offset + 6] = normal.x;
+    vbo[offset + 7] = normal.y;
+    vbo[offset + 8] = normal.z;
+    vbo[offset + 9] = color.r;
+    vbo[offset + 10] = color.g;
+    vbo[offset + 11] = color.b;
+    vbo[offset + 12] = color.a;
+}
+
+fn max_vertices() -> u32 {
+    return arrayLength(&vbo) / VERTEX_STRIDE;
+}
+
+fn vertex_position(index: u32) -> vec3<f32> {
+    let offset = index * VERTEX_STRIDE;
+    return vec3<f32>(vbo[offset], vbo[offset + 1], vbo[offset + 2]);
 }
 
 fn cube_vertices(vortex_size: vec3<f32>, vortex_origin: vec3<f32>) -> array<vec3<f32>, 8>{
@@ -50,23 +80,41 @@ fn cube_vertices(vortex_size: vec3<f32>, vortex_origin: vec3<f32>) -> array<vec3
     );
 }
 
-fn sdf(x: vec3<f32>) -> f32 {
-    return distance(x, polygonization_info.sphere_center) - polygonization_info.sphere_radius;
-}
-
 fn sdfs(vertices: array<vec3<f32>, 8>) -> array<f32, 8> {
+    let snake = polygonization_info.snake;
     return array<f32, 8>(
-        sdf(vertices[0]),
-        sdf(vertices[1]),
-        sdf(vertices[2]),
-        sdf(vertices[3]),
-        sdf(vertices[4]),
-        sdf(vertices[5]),
-        sdf(vertices[6]),
-        sdf(vertices[7]),
+        sdf(snake, vertices[0]),
+        sdf(snake, vertices[1]),
+        sdf(snake, vertices[2]),
+        sdf(snake, vertices[3]),
+        sdf(snake, vertices[4]),
+        sdf(snake, vertices[5]),
+        sdf(snake, vertices[6]),
+        sdf(snake, vertices[7]),
     );
 }
 
+fn closest_surface_point(snake: SnakeSdf, x: vec3<f32>) -> vec3<f32> {
+    let gradient = sdf_gradient(snake, x);
+    let gradient_length = length(gradient);
+    if gradient_length < 0.0001 {
+        return x;
+    }
+    return x - sdf(snake, x) * gradient / gradient_length;
+}
+
+// vertices don't survive between frames, so the vertex is followed along the body by its arc
+// length and offset from the centerline, which keeps sliding along the body in motion vectors.
+// A body of one point has no centerline, then the closest point on last frame's surface is used
+fn previous_position(x: vec3<f32>) -> vec3<f32> {
+    let snake = polygonization_info.snake;
+    let previous = previous_polygonization_info.snake;
+    if snake.point_count < 2u || previous.point_count < 2u {
+        return closest_surface_point(previous, x);
+    }
+    return follow_body(snake, previous, x);
+}
+
 fn edge_bitmask(index: u32) -> u32 {
     return 1u << index;
 }
@@ -77,7 +125,14 @@ fn get_intersection(p0: vec3<f32>, p1: vec3<f32>, sdf0: f32, sdf1: f32) -> vec3<
     return (1.0 - ratio) * p0 + ratio * p1;
 }
 
-fn write_quad_to_ibo(index: u32, point0: u32, point1: u32, point2: u32, point3: u32) {
+fn add_quad(point0: u32, point1: u32, point2: u32, point3: u32) {
+    if max(max(point0, point1), max(point2, point3)) == NO_VERTEX {
+        return;
+    }
+    let index = atomicAdd(&atomics[1], 1u);
+    if (index + 1u) * 6u > arrayLength(&ibo) {
+        return;
+    }
     ibo[index * 6] = point0;
     ibo[index * 6 + 1] = point1;
     ibo[index * 6 + 2] = point2;
@@ -128,7 +183,14 @@ fn find_vertices(@builtin(global_invocation_id) invocation_id: vec3<u32>, @built
     if intersections_count > 0 {
         let point = sum / f32(intersections_count);
         vbo_index = atomicAdd(&atomics[0], 1u);
-        set_vertex(vbo_index, point);
+        if vbo_index < max_vertices() {
+            let normal = normalize(sdf_gradient(polygonization_info.snake, point));
+            let color = sdf_color(polygonization_info.snake, point);
+            set_vertex(vbo_index, point, previous_position(point), normal, color);
+        } else {
+            vbo_index = NO_VERTEX;
+            intersections_bitmask = 0u;
+        }
     }
     let flat_index = flat_invocation_id(invocation_id, invocations_number);
     cells[flat_index] = CellInfo(vbo_index, intersections_bitmask);
@@ -145,8 +207,7 @@ fn connect_vertices(@builtin(global_invocation_id) invocation_id: vec3<u32>, @bu
             let vbo_index_point1 = cells[flat_invocation_id(invocation_id - vec3<u32>(0, 1, 0), invocations_number)].vbo_index;
             let vbo_index_point2 = cells[flat_invocation_id(invocation_id - vec3<u32>(0, 0, 1), invocations_number)].vbo_index;
             let vbo_index_point3 = cells[flat_invocation_id(invocation_id - vec3<u32>(0, 1, 1), invocations_number)].vbo_index;
-            let quad_index = atomicAdd(&atomics[1], 1u);
-            write_quad_to_ibo(quad_index, vbo_index_point0, vbo_index_point1, vbo_index_point2, vbo_index_point3);
+            add_quad(vbo_index_point0, vbo_index_point1, vbo_index_point2, vbo_index_point3);
         }
     }
     if ((cell1.intersections_bitmask & edge_bitmask(1u)) != 0u) {
@@ -155,8 +216,7 @@ fn connect_vertices(@builtin(global_invocation_id) invocation_id: vec3<u32>, @bu
             let vbo_index_point1 = cells[flat_invocation_id(invocation_id - vec3<u32>(1, 0, 0), invocations_number)].vbo_index;
             let vbo_index_point2 = cells[flat_invocation_id(invocation_id - vec3<u32>(0, 0, 1), invocations_number)].vbo_index;
             let vbo_index_point3 = cells[flat_invocation_id(invocation_id - vec3<u32>(1, 0, 1), invocations_number)].vbo_index;
-            let quad_index = atomicAdd(&atomics[1], 1u);
-            write_quad_to_ibo(quad_index, vbo_index_point0, vbo_index_point1, vbo_index_point2, vbo_index_point3);
+            add_quad(vbo_index_point0, vbo_index_point1, vbo_index_point2, vbo_index_point3);
         }
     }
     if ((cell1.intersections_bitmask & edge_bitmask(2u)) != 0u) {
@@ -165,15 +225,69 @@ fn connect_vertices(@builtin(global_invocation_id) invocation_id: vec3<u32>, @bu
             let vbo_index_point1 = cells[flat_invocation_id(invocation_id - vec3<u32>(1, 0, 0), invocations_number)].vbo_index;
             let vbo_index_point2 = cells[flat_invocation_id(invocation_id - vec3<u32>(0, 1, 0), invocations_number)].vbo_index;
             let vbo_index_point3 = cells[flat_invocation_id(invocation_id - vec3<u32>(1, 1, 0), invocations_number)].vbo_index;
-            let quad_index = atomicAdd(&atomics[1], 1u);
-            write_quad_to_ibo(quad_index, vbo_index_point0, vbo_index_point1, vbo_index_point2, vbo_index_point3);
+            add_quad(vbo_index_point0, vbo_index_point1, vbo_index_point2, vbo_index_point3);
+        }
+    }
+}
+
+// moves the vertex of the cell toward the average of the vertices in the 6 neighboring cells,
+// then back onto the surface. Reads only the vbo, so the order of invocations doesn't matter
+@compute @workgroup_size(8, 8, 8)
+fn relax_vertices(@builtin(global_invocation_id) invocation_id: vec3<u32>, @builtin(num_workgroups) num_workgroups: vec3<u32>) {
+    var neighbors = array<vec3<i32>, 6>(
+        vec3<i32>(1, 0, 0),
+        vec3<i32>(-1, 0, 0),
+        vec3<i32>(0, 1, 0),
+        vec3<i32>(0, -1, 0),
+        vec3<i32>(0, 0, 1),
+        vec3<i32>(0, 0, -1),
+    );
+
+    let invocations_number = num_workgroups * vec3<u32>(8, 8, 8);
+    let flat_index = flat_invocation_id(invocation_id, invocations_number);
+    let cell = cells[flat_index];
+    if cell.intersections_bitmask == 0u {
+        return;
+    }
+    let position = vertex_position(cell.vbo_index);
+    var sum = vec3<f32>(0.0, 0.0, 0.0);
+    var neighbors_count: u32 = 0;
+    for (var i: u32; i < 6; i++) {
+        let neighbor = vec3<i32>(invocation_id) + neighbors[i];
+        if any(neighbor < vec3<i32>(0)) || any(neighbor >= vec3<i32>(invocations_number)) {
+            continue;
+        }
+        let neighbor_cell = cells[flat_invocation_id(vec3<u32>(neighbor), invocations_number)];
+        if neighbor_cell.intersections_bitmask != 0u {
+            sum += vertex_position(neighbor_cell.vbo_index);
+            neighbors_count += 1u;
         }
     }
+    var relaxed = position;
+    if neighbors_count > 0u {
+        relaxed = mix(position, sum / f32(neighbors_count), RELAXATION_FACTOR);
+    }
+    let snake = polygonization_info.snake;
+    relaxed_positions[flat_index] = vec4<f32>(closest_surface_point(snake, relaxed), 1.0);
+}
+
+@compute @workgroup_size(8, 8, 8)
+fn apply_relaxed_vertices(@builtin(global_invocation_id) invocation_id: vec3<u32>, @builtin(num_workgroups) num_workgroups: vec3<u32>) {
+    let invocations_number = num_workgroups * vec3<u32>(8, 8, 8);
+    let flat_index = flat_invocation_id(invocation_id, invocations_number);
+    let cell = cells[flat_index];
+    if cell.intersections_bitmask == 0u {
+        return;
+    }
+    let snake = polygonization_info.snake;
+    let point = relaxed_positions[flat_index].xyz;
+    let normal = normalize(sdf_gradient(snake, point));
+    set_vertex(cell.vbo_index, point, previous_position(point), normal, sdf_color(snake, point));
 }
 
 @compute @workgroup_size(1, 1, 1)
 fn prepare_indirect_buffer() {
-    indirect.index_count = atomics[1] * 6u;
+    indirect.index_count = min(atomicLoad(&atomics[1]), arrayLength(&ibo) / 6u) * 6u;
     indirect.instance_count = 1u;
     indirect.first_index = 0u;
     indirect.vertex_offset = 0i;
@@ -0,0 +1,48 @@
+#import bevy_pbr::{
+    mesh_functions,
+    view_transformations::position_world_to_clip,
+}
+
+@group(2) @binding(0) var<uniform> grid_origin: vec3<f32>;
+@group(2) @binding(1) var<uniform> cell_size: vec3<f32>;
+// matches SnakeDebugView
+@group(2) @binding(2) var<uniform> mode: u32;
+
+const MODE_NORMALS: u32 = 1u;
+const MODE_CELL_PLACEMENT: u32 = 2u;
+
+struct Vertex {
+    @builtin(instance_index) instance_index: u32,
+    @location(0) position: vec3<f32>,
+    @location(1) normal: vec3<f32>,
+}
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) color: vec4<f32>,
+}
+
+@vertex
+fn vertex(vertex: Vertex) -> VertexOutput {
+    var out: VertexOutput;
+    let model = mesh_functions::get_model_matrix(vertex.instance_index);
+    let world_position = mesh_functions::mesh_position_local_to_world(model, vec4<f32>(vertex.position, 1.0));
+    out.position = position_world_to_clip(world_position.xyz);
+
+    if mode == MODE_NORMALS {
+        let world_normal = mesh_functions::mesh_normal_local_to_world(vertex.normal, vertex.instance_index);
+        out.color = vec4<f32>(world_normal * 0.5 + 0.5, 1.0);
+    } else if mode == MODE_CELL_PLACEMENT {
+        // (0, 0, 0) is the cell's bottom left corner, (1, 1, 1) is the opposite one
+        let cell_local = fract((vertex.position - grid_origin) / cell_size);
+        out.color = vec4<f32>(cell_local, 1.0);
+    } else {
+        out.color = vec4<f32>(1.0, 0.0, 1.0, 1.0);
+    }
+    return out;
+}
+
+@fragment
+fn fragment(in: VertexOutput) -> @location(0) vec4<f32> {
+    return in.color;
+}
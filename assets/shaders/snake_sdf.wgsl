@@ -0,0 +1,240 @@
+#define_import_path snake_mesh::sdf
+
+// matches MAX_BODY_POINTS
+const MAX_BODY_POINTS: u32 = 128u;
+// matches RADIUS_SCALE_VECTORS
+const RADIUS_SCALE_VECTORS: u32 = 32u;
+// matches GRADIENT_STEP
+const GRADIENT_STEP: f32 = 0.001;
+
+// matches SnakeEyesUniform
+struct SnakeEyes {
+    // xyz is the center, w is the radius. Zero radius means no eyes
+    left: vec4<f32>,
+    right: vec4<f32>,
+    openness: f32,
+    smoothness: f32,
+}
+
+// chain of round cones going through the body points, matches SnakeSdfUniform
+struct SnakeSdf {
+    radius: f32,
+    point_count: u32,
+    // xyz is the position, w is the arc length from the first point
+    points: array<vec4<f32>, MAX_BODY_POINTS>,
+    // multiplier of the radius at every point, packed 4 per vector
+    radius_scales: array<vec4<f32>, RADIUS_SCALE_VECTORS>,
+    // linear rgba at every point
+    colors: array<vec4<f32>, MAX_BODY_POINTS>,
+    eyes: SnakeEyes,
+    // x is the threshold, y the frequency. Zero frequency means no dissolve
+    dissolve: vec4<f32>,
+}
+
+// pcg hash, integer math so the cpu gets exactly the same values
+fn hash(x: u32) -> u32 {
+    let state = x * 747796405u + 2891336453u;
+    let word = ((state >> ((state >> 28u) + 4u)) ^ state) * 277803737u;
+    return (word >> 22u) ^ word;
+}
+
+fn corner_value(cell: vec3<i32>) -> f32 {
+    let h = hash(bitcast<u32>(cell.x) ^ hash(bitcast<u32>(cell.y) ^ hash(bitcast<u32>(cell.z))));
+    return f32(h) / 4294967295.0;
+}
+
+// smoothed value noise in [0, 1]
+fn value_noise(x: vec3<f32>) -> f32 {
+    let floor_x = floor(x);
+    let f = x - floor_x;
+    let u = f * f * (3.0 - 2.0 * f);
+    let cell = vec3<i32>(floor_x);
+    let y0 = mix(
+        mix(corner_value(cell), corner_value(cell + vec3<i32>(1, 0, 0)), u.x),
+        mix(corner_value(cell + vec3<i32>(0, 1, 0)), corner_value(cell + vec3<i32>(1, 1, 0)), u.x),
+        u.y,
+    );
+    let y1 = mix(
+        mix(corner_value(cell + vec3<i32>(0, 0, 1)), corner_value(cell + vec3<i32>(1, 0, 1)), u.x),
+        mix(corner_value(cell + vec3<i32>(0, 1, 1)), corner_value(cell + vec3<i32>(1, 1, 1)), u.x),
+        u.y,
+    );
+    return mix(y0, y1, u.z);
+}
+
+// eats holes into the surface where the noise is below the threshold
+fn dissolve_distance(dissolve: vec4<f32>, x: vec3<f32>, distance: f32) -> f32 {
+    let threshold = dissolve.x;
+    let frequency = dissolve.y;
+    if frequency <= 0.0 {
+        return distance;
+    }
+    return max(distance, (threshold - value_noise(x * frequency)) / frequency);
+}
+
+// polynomial smooth min, https://iquilezles.org/articles/smin/
+fn smooth_union(a: f32, b: f32, k: f32) -> f32 {
+    let k_ = max(k, 1e-4);
+    let h = clamp(0.5 + 0.5 * (b - a) / k_, 0.0, 1.0);
+    return mix(b, a, h) - k_ * h * (1.0 - h);
+}
+
+// not exact, but a good enough bound for sphere tracing
+fn ellipsoid_distance(x: vec3<f32>, radii: vec3<f32>) -> f32 {
+    let k0 = length(x / radii);
+    let k1 = length(x / (radii * radii));
+    if k1 == 0.0 {
+        return -min(radii.x, min(radii.y, radii.z));
+    }
+    return k0 * (k0 - 1.0) / k1;
+}
+
+// closing eyes get squashed vertically
+fn eyes_distance(eyes: SnakeEyes, x: vec3<f32>, body_distance: f32) -> f32 {
+    let radius = eyes.left.w;
+    if radius <= 0.0 {
+        return body_distance;
+    }
+    let lid = 0.15 + 0.85 * clamp(eyes.openness, 0.0, 1.0);
+    let radii = vec3<f32>(radius, radius * lid, radius);
+    let left = smooth_union(body_distance, ellipsoid_distance(x - eyes.left.xyz, radii), eyes.smoothness);
+    return smooth_union(left, ellipsoid_distance(x - eyes.right.xyz, radii), eyes.smoothness);
+}
+
+fn point_radius(snake: SnakeSdf, index: u32) -> f32 {
+    return snake.radius * snake.radius_scales[index / 4u][index % 4u];
+}
+
+// exact distance to the convex hull of two spheres, https://iquilezles.org/articles/distfunctions/
+fn round_cone_distance(x: vec3<f32>, a: vec3<f32>, b: vec3<f32>, radius_a: f32, radius_b: f32) -> f32 {
+    let ba = b - a;
+    let l2 = dot(ba, ba);
+    let rr = radius_a - radius_b;
+    let a2 = l2 - rr * rr;
+    // one sphere is inside of the other one
+    if a2 <= 1e-8 {
+        return min(distance(x, a) - radius_a, distance(x, b) - radius_b);
+    }
+    let il2 = 1.0 / l2;
+    let pa = x - a;
+    let y = dot(pa, ba);
+    let z = y - l2;
+    let w = pa * l2 - ba * y;
+    let x2 = dot(w, w);
+    let y2 = y * y * l2;
+    let z2 = z * z * l2;
+    let k = sign(rr) * rr * rr * x2;
+    if sign(z) * a2 * z2 > k {
+        return sqrt(x2 + z2) * il2 - radius_b;
+    }
+    if sign(y) * a2 * y2 < k {
+        return sqrt(x2 + y2) * il2 - radius_a;
+    }
+    return (sqrt(x2 * a2 * il2) + y * rr) * il2 - radius_a;
+}
+
+fn body_distance(snake: SnakeSdf, x: vec3<f32>) -> f32 {
+    if snake.point_count == 0u {
+        return 1e10;
+    }
+    if snake.point_count == 1u {
+        return distance(x, snake.points[0].xyz) - point_radius(snake, 0u);
+    }
+    var result = 1e10;
+    for (var i = 0u; i + 1u < snake.point_count; i++) {
+        let a = snake.points[i].xyz;
+        let b = snake.points[i + 1u].xyz;
+        result = min(result, round_cone_distance(x, a, b, point_radius(snake, i), point_radius(snake, i + 1u)));
+    }
+    return result;
+}
+
+fn sdf(snake: SnakeSdf, x: vec3<f32>) -> f32 {
+    let distance = eyes_distance(snake.eyes, x, body_distance(snake, x));
+    return dissolve_distance(snake.dissolve, x, distance);
+}
+
+// interpolated along the segment whose surface is closest
+fn sdf_color(snake: SnakeSdf, x: vec3<f32>) -> vec4<f32> {
+    if snake.point_count == 0u {
+        return vec4<f32>(1.0);
+    }
+    var closest_distance = 3.40282347e+38;
+    var color = snake.colors[0];
+    for (var i = 0u; i + 1u < snake.point_count; i++) {
+        let a = snake.points[i].xyz;
+        let b = snake.points[i + 1u].xyz;
+        let distance = round_cone_distance(x, a, b, point_radius(snake, i), point_radius(snake, i + 1u));
+        if distance < closest_distance {
+            closest_distance = distance;
+            let ba = b - a;
+            let h = clamp(dot(x - a, ba) / max(dot(ba, ba), 1e-8), 0.0, 1.0);
+            color = mix(snake.colors[i], snake.colors[i + 1u], h);
+        }
+    }
+    return color;
+}
+
+// shortest rotation taking the unit vector `from_dir` to `to_dir`, rodrigues' formula
+fn rotate_between(v: vec3<f32>, from_dir: vec3<f32>, to_dir: vec3<f32>) -> vec3<f32> {
+    let c = dot(from_dir, to_dir);
+    // degenerate or reversed segments have no single rotation to pick
+    if all(from_dir == vec3<f32>(0.0)) || all(to_dir == vec3<f32>(0.0)) || c < -0.9999 {
+        return v;
+    }
+    let k = cross(from_dir, to_dir);
+    return v * c + cross(k, v) + k * dot(k, v) / (1.0 + c);
+}
+
+fn normalize_or_zero(v: vec3<f32>) -> vec3<f32> {
+    let l = length(v);
+    if l < 1e-8 {
+        return vec3<f32>(0.0);
+    }
+    return v / l;
+}
+
+// where x was on the previous body: same arc length from the first point and the same offset
+// from the centerline, turned with the segment. Both bodies need at least two points
+fn follow_body(snake: SnakeSdf, previous: SnakeSdf, x: vec3<f32>) -> vec3<f32> {
+    var closest_distance = 3.40282347e+38;
+    var arc_length = 0.0;
+    var offset = vec3<f32>(0.0);
+    var tangent = vec3<f32>(0.0);
+    for (var i = 0u; i + 1u < snake.point_count; i++) {
+        let a = snake.points[i];
+        let b = snake.points[i + 1u];
+        let ba = b.xyz - a.xyz;
+        let h = clamp(dot(x - a.xyz, ba) / max(dot(ba, ba), 1e-8), 0.0, 1.0);
+        let center = a.xyz + ba * h;
+        let distance = length(x - center);
+        if distance < closest_distance {
+            closest_distance = distance;
+            arc_length = mix(a.w, b.w, h);
+            offset = x - center;
+            tangent = normalize_or_zero(ba);
+        }
+    }
+    // the last segment is extended when the previous body was shorter
+    var i = 0u;
+    while i + 2u < previous.point_count && arc_length > previous.points[i + 1u].w {
+        i++;
+    }
+    let a = previous.points[i];
+    let b = previous.points[i + 1u];
+    let ba = b.xyz - a.xyz;
+    let h = (arc_length - a.w) / max(b.w - a.w, 1e-8);
+    return a.xyz + ba * h + rotate_between(offset, tangent, normalize_or_zero(ba));
+}
+
+// central differences
+fn sdf_gradient(snake: SnakeSdf, x: vec3<f32>) -> vec3<f32> {
+    let dx = vec3<f32>(GRADIENT_STEP, 0.0, 0.0);
+    let dy = vec3<f32>(0.0, GRADIENT_STEP, 0.0);
+    let dz = vec3<f32>(0.0, 0.0, GRADIENT_STEP);
+    return vec3<f32>(
+        sdf(snake, x + dx) - sdf(snake, x - dx),
+        sdf(snake, x + dy) - sdf(snake, x - dy),
+        sdf(snake, x + dz) - sdf(snake, x - dz),
+    ) / (2.0 * GRADIENT_STEP);
+}
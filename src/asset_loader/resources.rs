@@ -2,18 +2,9 @@ use std::collections::HashMap;
 
 use bevy::prelude::*;
 
+// the snake is generated, no scene needs loading at the moment
 #[derive(Hash, Eq, PartialEq, Clone, Copy, Debug)]
-pub enum SceneAssets {
-    SnakeMainBody,
-    SnakeHead1,
-    SnakeHead2,
-    SnakeHead3,
-    SnakeHead4,
-    SnakeHead5,
-    SnakeHead6,
-    SnakeHead7,
-    SnakeHead8,
-}
+pub enum SceneAssets {}
 
 #[derive(Resource)]
 pub struct AssetsStorage {
@@ -5,6 +5,22 @@ pub struct Cell {
     pub pos: IVec2,
 }
 
+// Blocks its Cell, snakes running into it crash, registered as an OccupantKind::Wall occupant
+#[derive(Component, Clone, Copy, Debug, PartialEq)]
+pub struct Obstacle;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum OccupantKind {
+    SnakeHead,
+    SnakeBody,
+    Food,
+    Wall,
+}
+
+// Registers the entity in the Field occupancy at its Cell, or at its Transform when it has no Cell
+#[derive(Component, Clone, Copy, Debug, PartialEq)]
+pub struct Occupant(pub OccupantKind);
+
 #[allow(dead_code)]
 impl Cell {
     pub fn new(x: i32, y: i32) -> Self {
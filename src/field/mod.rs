@@ -4,19 +4,37 @@ mod systems;
 
 use bevy::prelude::*;
 
-pub use components::Cell;
+pub use components::{Cell, Obstacle, Occupant, OccupantKind};
 pub use resources::Field;
 
-use systems::setup;
+use systems::{
+    occupy_obstacles, release_occupants, setup, track_cell_occupants, track_moving_occupants,
+};
 
 use crate::plugins::TiledMaterialPlugin;
 
+// Field occupancy is up to date after this set
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct OccupancySet;
+
 pub struct FieldPlugin;
 
 impl Plugin for FieldPlugin {
     fn build(&self, app: &mut App) {
         app.add_plugins(TiledMaterialPlugin)
             .add_systems(Startup, setup)
+            .add_systems(
+                FixedUpdate,
+                (
+                    occupy_obstacles,
+                    track_cell_occupants,
+                    track_moving_occupants,
+                )
+                    .chain()
+                    .in_set(OccupancySet),
+            )
+            // removals are only kept for two updates, which can pass without a fixed one
+            .add_systems(PostUpdate, release_occupants)
             .insert_resource(Field::new(
                 IVec2 { x: 10, y: 10 },
                 Vec2 { x: 10.0, y: 10.0 },
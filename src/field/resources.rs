@@ -1,6 +1,12 @@
-use bevy::prelude::*;
+use bevy::{prelude::*, utils::HashMap};
 
-use super::Cell;
+use super::{Cell, OccupantKind};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CellOccupant {
+    pub entity: Entity,
+    pub kind: OccupantKind,
+}
 
 #[derive(Resource)]
 pub struct Field {
@@ -8,6 +14,9 @@ pub struct Field {
     size: Vec2,
     translation: Vec2,
     cell_size: f32,
+    // row by row, kept up to date from Occupant components
+    occupancy: Vec<Vec<CellOccupant>>,
+    occupied_cells: HashMap<Entity, IVec2>,
 }
 
 impl Field {
@@ -21,6 +30,8 @@ impl Field {
             size,
             translation,
             cell_size: cell_size.x,
+            occupancy: vec![Vec::new(); (dim.x * dim.y) as usize],
+            occupied_cells: HashMap::default(),
         }
     }
 
@@ -34,22 +45,73 @@ impl Field {
         Vec2 { x, y }
     }
 
+    // can be outside of the field, see contains()
     pub fn cell(&self, translation: Vec2) -> Cell {
         let cell_size = self.cell_size();
         let local_translation = translation - self.bottom_left();
         Cell {
-            pos: IVec2 {
-                x: (local_translation.x / cell_size) as i32,
-                y: (local_translation.y / cell_size) as i32,
-            },
+            pos: (local_translation / cell_size).floor().as_ivec2(),
         }
     }
 
+    pub fn contains(&self, cell: &Cell) -> bool {
+        cell.pos.cmpge(IVec2::ZERO).all() && cell.pos.cmplt(self.dim).all()
+    }
+
     #[allow(dead_code)]
     pub fn cell_local_translation(&self, translation: Vec2) -> Vec2 {
         (translation - self.translation) % self.cell_size()
     }
 
+    // empty outside of the field
+    pub fn occupants(&self, cell: &Cell) -> &[CellOccupant] {
+        match self.cell_index(cell) {
+            Some(index) => &self.occupancy[index],
+            None => &[],
+        }
+    }
+
+    pub fn is_occupied_by(&self, cell: &Cell, kind: OccupantKind) -> bool {
+        self.occupants(cell)
+            .iter()
+            .any(|occupant| occupant.kind == kind)
+    }
+
+    pub fn free_cells(&self) -> impl Iterator<Item = Cell> + '_ {
+        self.occupancy
+            .iter()
+            .enumerate()
+            .filter(|(_, occupants)| occupants.is_empty())
+            .map(|(index, _)| Cell::new(index as i32 % self.dim.x, index as i32 / self.dim.x))
+    }
+
+    // moves the entity if it's already somewhere else, cells outside of the field only vacate
+    pub fn occupy(&mut self, entity: Entity, kind: OccupantKind, cell: &Cell) {
+        if self.occupied_cells.get(&entity) == Some(&cell.pos) {
+            return;
+        }
+        self.vacate(entity);
+        let Some(index) = self.cell_index(cell) else {
+            return;
+        };
+        self.occupancy[index].push(CellOccupant { entity, kind });
+        self.occupied_cells.insert(entity, cell.pos);
+    }
+
+    pub fn vacate(&mut self, entity: Entity) {
+        let Some(pos) = self.occupied_cells.remove(&entity) else {
+            return;
+        };
+        if let Some(index) = self.cell_index(&Cell { pos }) {
+            self.occupancy[index].retain(|occupant| occupant.entity != entity);
+        }
+    }
+
+    fn cell_index(&self, cell: &Cell) -> Option<usize> {
+        self.contains(cell)
+            .then(|| (cell.j() * self.dim.x + cell.i()) as usize)
+    }
+
     pub fn cell_size(&self) -> f32 {
         self.cell_size
     }
@@ -82,6 +144,40 @@ fn test_field_translation() {
     assert_eq!(field.translation_of_cell(&cell), Vec2 { x: 25.0, y: 25.0 });
 }
 
+#[test]
+fn test_field_bounds() {
+    use super::*;
+    let field = Field::new(IVec2::new(10, 10), Vec2::new(100.0, 100.0), Vec2::ZERO);
+    let cell = field.cell(Vec2::new(-53.0, 0.0));
+    assert_eq!(cell, Cell::new(-1, 5));
+    assert!(!field.contains(&cell));
+    assert!(field.contains(&Cell::new(9, 0)));
+    assert!(!field.contains(&Cell::new(9, 10)));
+}
+
+#[test]
+fn test_field_occupancy() {
+    use super::*;
+    let mut field = Field::new(IVec2::new(4, 2), Vec2::new(4.0, 2.0), Vec2::ZERO);
+    let snake = Entity::from_raw(1);
+    let food = Entity::from_raw(2);
+    field.occupy(snake, OccupantKind::SnakeHead, &Cell::new(1, 1));
+    field.occupy(food, OccupantKind::Food, &Cell::new(1, 1));
+    assert_eq!(field.occupants(&Cell::new(1, 1)).len(), 2);
+    assert_eq!(field.free_cells().count(), 7);
+
+    field.occupy(snake, OccupantKind::SnakeHead, &Cell::new(3, 0));
+    assert!(field.is_occupied_by(&Cell::new(3, 0), OccupantKind::SnakeHead));
+    assert!(!field.is_occupied_by(&Cell::new(1, 1), OccupantKind::SnakeHead));
+    assert!(field.free_cells().all(|cell| cell != Cell::new(3, 0)));
+
+    // leaving the field
+    field.occupy(snake, OccupantKind::SnakeHead, &Cell::new(4, 0));
+    field.vacate(food);
+    assert_eq!(field.free_cells().count(), 8);
+    assert!(field.occupants(&Cell::new(-1, 0)).is_empty());
+}
+
 #[test]
 fn test_field_local_coordinates() {
     use super::*;
@@ -1,6 +1,7 @@
 use bevy::pbr::ExtendedMaterial;
 use bevy::prelude::*;
 
+use super::components::{Cell, Obstacle, Occupant, OccupantKind};
 use super::resources::Field;
 use crate::plugins::TiledMaterialExtension;
 
@@ -59,3 +60,43 @@ pub fn setup(
             });
         });
 }
+
+pub fn occupy_obstacles(
+    mut commands: Commands,
+    obstacles: Query<Entity, (Added<Obstacle>, Without<Occupant>)>,
+) {
+    for obstacle in obstacles.iter() {
+        commands
+            .entity(obstacle)
+            .insert(Occupant(OccupantKind::Wall));
+    }
+}
+
+pub fn track_cell_occupants(
+    mut field: ResMut<Field>,
+    occupants: Query<(Entity, &Occupant, &Cell), Or<(Changed<Cell>, Changed<Occupant>)>>,
+) {
+    for (entity, occupant, cell) in occupants.iter() {
+        field.occupy(entity, occupant.0, cell);
+    }
+}
+
+// body fragments only have a Transform
+pub fn track_moving_occupants(
+    mut field: ResMut<Field>,
+    occupants: Query<
+        (Entity, &Occupant, &Transform),
+        (Without<Cell>, Or<(Changed<Transform>, Changed<Occupant>)>),
+    >,
+) {
+    for (entity, occupant, transform) in occupants.iter() {
+        let cell = field.cell(transform.translation.xz());
+        field.occupy(entity, occupant.0, &cell);
+    }
+}
+
+pub fn release_occupants(mut field: ResMut<Field>, mut removed: RemovedComponents<Occupant>) {
+    for entity in removed.read() {
+        field.vacate(entity);
+    }
+}
@@ -0,0 +1,20 @@
+use bevy::prelude::*;
+
+#[derive(Component)]
+pub struct Food;
+
+#[derive(Component, Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum FoodType {
+    Banana,
+    Strawberry,
+}
+
+impl FoodType {
+    // PreySwallowed::size of the snake eating it
+    pub fn size(&self) -> f32 {
+        match self {
+            FoodType::Banana => 1.0,
+            FoodType::Strawberry => 0.5,
+        }
+    }
+}
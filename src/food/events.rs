@@ -0,0 +1,10 @@
+use bevy::prelude::*;
+
+use super::FoodType;
+
+// Sent after the food was despawned
+#[derive(Event, Clone, Copy, Debug)]
+pub struct FoodEaten {
+    pub snake: Entity,
+    pub food_type: FoodType,
+}
@@ -0,0 +1,34 @@
+mod components;
+mod events;
+mod resources;
+mod systems;
+
+use bevy::prelude::*;
+
+pub use components::{Food, FoodType};
+pub use events::FoodEaten;
+pub use resources::FoodSpawnRules;
+
+use crate::field::OccupancySet;
+use crate::states::GameState;
+
+pub struct FoodPlugin;
+impl Plugin for FoodPlugin {
+    fn build(&self, app: &mut App) {
+        let rules = resources::FoodSpawnRules::default();
+        app.init_resource::<resources::FoodAssets>()
+            .insert_resource(resources::FoodSpawnTimer(Timer::from_seconds(
+                rules.interval,
+                TimerMode::Repeating,
+            )))
+            .insert_resource(rules)
+            .add_event::<FoodEaten>()
+            .add_systems(
+                FixedUpdate,
+                (systems::spawn_food, systems::eat_food)
+                    .after(OccupancySet)
+                    .run_if(in_state(GameState::InGame)),
+            )
+            .add_systems(Update, systems::animate_food);
+    }
+}
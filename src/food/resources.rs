@@ -0,0 +1,151 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+use rand::{distributions::WeightedIndex, prelude::Distribution, seq::IteratorRandom, Rng};
+
+use super::FoodType;
+use crate::field::Cell;
+
+#[derive(Resource, Default)]
+pub struct FoodSpawnTimer(pub Timer);
+
+// Replace the resource to switch game modes, the spawn timer follows the interval
+#[derive(Resource, Clone, Debug, PartialEq)]
+pub struct FoodSpawnRules {
+    // seconds between spawn attempts
+    pub interval: f32,
+    // nothing spawns while this much food is on the field
+    pub max_food: usize,
+    // in cells, manhattan distance from every snake head
+    pub min_head_distance: i32,
+    // relative chance of every food type
+    pub weights: Vec<(FoodType, f32)>,
+}
+
+impl Default for FoodSpawnRules {
+    fn default() -> Self {
+        Self::classic()
+    }
+}
+
+impl FoodSpawnRules {
+    pub fn classic() -> Self {
+        Self {
+            interval: 2.0,
+            max_food: 3,
+            min_head_distance: 3,
+            weights: vec![(FoodType::Strawberry, 3.0), (FoodType::Banana, 1.0)],
+        }
+    }
+
+    // lots of food everywhere
+    #[allow(dead_code)]
+    pub fn frenzy() -> Self {
+        Self {
+            interval: 0.5,
+            max_food: 15,
+            min_head_distance: 1,
+            weights: vec![(FoodType::Strawberry, 1.0), (FoodType::Banana, 1.0)],
+        }
+    }
+
+    // uniformly random among the free cells far enough from the heads
+    pub fn pick_cell(
+        &self,
+        free_cells: impl Iterator<Item = Cell>,
+        heads: &[IVec2],
+        rng: &mut impl Rng,
+    ) -> Option<Cell> {
+        free_cells
+            .filter(|cell| {
+                heads.iter().all(|head| {
+                    let distance = (cell.pos - *head).abs();
+                    distance.x + distance.y >= self.min_head_distance
+                })
+            })
+            .choose(rng)
+    }
+
+    pub fn pick_food_type(&self, rng: &mut impl Rng) -> Option<FoodType> {
+        let index = WeightedIndex::new(self.weights.iter().map(|(_, weight)| *weight)).ok()?;
+        Some(self.weights[index.sample(rng)].0)
+    }
+}
+
+pub struct FoodAsset {
+    pub mesh: Handle<Mesh>,
+    pub material: Handle<StandardMaterial>,
+}
+
+// Meshes sized for a field cell of 1.0, scaled by Field::cell_size when spawned
+#[derive(Resource)]
+pub struct FoodAssets(pub HashMap<FoodType, FoodAsset>);
+
+impl FromWorld for FoodAssets {
+    fn from_world(world: &mut World) -> Self {
+        let banana = world
+            .resource_mut::<Assets<Mesh>>()
+            .add(Capsule3d::new(0.12, 0.4));
+        let strawberry = world.resource_mut::<Assets<Mesh>>().add(Sphere::new(0.2));
+        let mut materials = world.resource_mut::<Assets<StandardMaterial>>();
+        let mut assets = HashMap::new();
+        assets.insert(
+            FoodType::Banana,
+            FoodAsset {
+                mesh: banana,
+                material: materials.add(Color::YELLOW),
+            },
+        );
+        assets.insert(
+            FoodType::Strawberry,
+            FoodAsset {
+                mesh: strawberry,
+                material: materials.add(Color::CRIMSON),
+            },
+        );
+        Self(assets)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::*;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use super::FoodSpawnRules;
+    use crate::field::Cell;
+    use crate::food::FoodType;
+
+    #[test]
+    fn test_pick_cell() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let rules = FoodSpawnRules {
+            min_head_distance: 2,
+            ..FoodSpawnRules::classic()
+        };
+        assert_eq!(rules.pick_cell(std::iter::empty(), &[], &mut rng), None);
+
+        let free = [Cell::new(2, 2), Cell::new(1, 0)];
+        for _ in 0..10 {
+            let cell = rules.pick_cell(free.into_iter(), &[IVec2::new(0, 0)], &mut rng);
+            assert_eq!(cell, Some(Cell::new(2, 2)));
+        }
+    }
+
+    #[test]
+    fn test_pick_food_type() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let rules = FoodSpawnRules {
+            weights: vec![(FoodType::Banana, 0.0), (FoodType::Strawberry, 1.0)],
+            ..FoodSpawnRules::classic()
+        };
+        for _ in 0..10 {
+            assert_eq!(rules.pick_food_type(&mut rng), Some(FoodType::Strawberry));
+        }
+        let empty = FoodSpawnRules {
+            weights: Vec::new(),
+            ..FoodSpawnRules::classic()
+        };
+        assert_eq!(empty.pick_food_type(&mut rng), None);
+    }
+}
@@ -0,0 +1,101 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use rand::thread_rng;
+
+use super::components::{Food, FoodType};
+use super::events::FoodEaten;
+use super::resources::{FoodAssets, FoodSpawnRules, FoodSpawnTimer};
+
+use crate::field::{Cell, Field, Occupant, OccupantKind};
+use crate::player::{MovedOntoNewCellEvent, Player};
+use crate::snake_mesh::PreySwallowed;
+
+pub fn spawn_food(
+    mut commands: Commands,
+    time: Res<Time>,
+    rules: Res<FoodSpawnRules>,
+    mut timer: ResMut<FoodSpawnTimer>,
+    field: Res<Field>,
+    food_assets: Res<FoodAssets>,
+    food_query: Query<(), With<Food>>,
+    heads: Query<&Cell, With<Player>>,
+) {
+    if rules.is_changed() {
+        timer
+            .0
+            .set_duration(Duration::from_secs_f32(rules.interval.max(f32::EPSILON)));
+    }
+    if !timer.0.tick(time.delta()).just_finished() {
+        return;
+    }
+    if food_query.iter().count() >= rules.max_food {
+        return;
+    }
+    let heads: Vec<IVec2> = heads.iter().map(|cell| cell.pos).collect();
+    let mut rng = thread_rng();
+    let Some(cell) = rules.pick_cell(field.free_cells(), &heads, &mut rng) else {
+        return;
+    };
+    let Some(food_type) = rules.pick_food_type(&mut rng) else {
+        return;
+    };
+    let Some(asset) = food_assets.0.get(&food_type) else {
+        return;
+    };
+    let translation = field.translation_of_cell(&cell);
+    commands.spawn((
+        Food,
+        food_type,
+        cell,
+        Occupant(OccupantKind::Food),
+        PbrBundle {
+            mesh: asset.mesh.clone(),
+            material: asset.material.clone(),
+            // same height as the snake head
+            transform: Transform::from_xyz(translation.x, 0.5, translation.y)
+                .with_scale(Vec3::splat(field.cell_size())),
+            ..default()
+        },
+    ));
+}
+
+pub fn eat_food(
+    mut commands: Commands,
+    mut moved_events: EventReader<MovedOntoNewCellEvent>,
+    mut field: ResMut<Field>,
+    food_query: Query<&FoodType, With<Food>>,
+    mut eaten_events: EventWriter<FoodEaten>,
+    mut prey_events: EventWriter<PreySwallowed>,
+) {
+    for moved in moved_events.read() {
+        let foods: Vec<Entity> = field
+            .occupants(&moved.cell)
+            .iter()
+            .filter(|occupant| occupant.kind == OccupantKind::Food)
+            .map(|occupant| occupant.entity)
+            .collect();
+        for food in foods {
+            let Ok(food_type) = food_query.get(food) else {
+                continue;
+            };
+            // the cell is free for spawn_food right away, not only after the despawn
+            field.vacate(food);
+            commands.entity(food).despawn_recursive();
+            eaten_events.send(FoodEaten {
+                snake: moved.snake,
+                food_type: *food_type,
+            });
+            prey_events.send(PreySwallowed {
+                snake: moved.snake,
+                size: food_type.size(),
+            });
+        }
+    }
+}
+
+pub fn animate_food(time: Res<Time>, mut food_query: Query<&mut Transform, With<Food>>) {
+    for mut transform in food_query.iter_mut() {
+        transform.rotate_y(time.delta_seconds());
+    }
+}
@@ -1,9 +1,242 @@
 use bevy::prelude::*;
 
-#[derive(Component, Copy, Clone, Debug)]
+use std::collections::VecDeque;
+
+#[derive(Component, Copy, Clone, Debug, PartialEq, Eq)]
 pub enum RequestDirection {
     Left,
     Right,
     Up,
     Down,
 }
+
+impl RequestDirection {
+    pub fn opposite(&self) -> Self {
+        match self {
+            RequestDirection::Left => RequestDirection::Right,
+            RequestDirection::Right => RequestDirection::Left,
+            RequestDirection::Up => RequestDirection::Down,
+            RequestDirection::Down => RequestDirection::Up,
+        }
+    }
+
+    // as seen on the screen
+    pub fn turned_left(&self) -> Self {
+        match self {
+            RequestDirection::Left => RequestDirection::Down,
+            RequestDirection::Right => RequestDirection::Up,
+            RequestDirection::Up => RequestDirection::Left,
+            RequestDirection::Down => RequestDirection::Right,
+        }
+    }
+}
+
+// Keys or gamepad steering one player's snake
+#[derive(Component, Copy, Clone, Debug, PartialEq)]
+pub enum InputBindings {
+    Arrows,
+    Wasd,
+    // d-pad of the gamepad with this id
+    Gamepad(usize),
+}
+
+impl InputBindings {
+    pub fn just_pressed(
+        &self,
+        keys: &ButtonInput<KeyCode>,
+        gamepad_buttons: &ButtonInput<GamepadButton>,
+    ) -> Option<RequestDirection> {
+        [
+            RequestDirection::Left,
+            RequestDirection::Right,
+            RequestDirection::Up,
+            RequestDirection::Down,
+        ]
+        .into_iter()
+        .find(|direction| self.is_just_pressed(*direction, keys, gamepad_buttons))
+    }
+
+    fn is_just_pressed(
+        &self,
+        direction: RequestDirection,
+        keys: &ButtonInput<KeyCode>,
+        gamepad_buttons: &ButtonInput<GamepadButton>,
+    ) -> bool {
+        match self {
+            InputBindings::Arrows => keys.just_pressed(match direction {
+                RequestDirection::Left => KeyCode::ArrowLeft,
+                RequestDirection::Right => KeyCode::ArrowRight,
+                RequestDirection::Up => KeyCode::ArrowUp,
+                RequestDirection::Down => KeyCode::ArrowDown,
+            }),
+            InputBindings::Wasd => keys.just_pressed(match direction {
+                RequestDirection::Left => KeyCode::KeyA,
+                RequestDirection::Right => KeyCode::KeyD,
+                RequestDirection::Up => KeyCode::KeyW,
+                RequestDirection::Down => KeyCode::KeyS,
+            }),
+            InputBindings::Gamepad(id) => gamepad_buttons.just_pressed(GamepadButton::new(
+                Gamepad::new(*id),
+                match direction {
+                    RequestDirection::Left => GamepadButtonType::DPadLeft,
+                    RequestDirection::Right => GamepadButtonType::DPadRight,
+                    RequestDirection::Up => GamepadButtonType::DPadUp,
+                    RequestDirection::Down => GamepadButtonType::DPadDown,
+                },
+            )),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum ReversalPolicy {
+    // a request for the opposite of the heading is dropped
+    #[default]
+    Reject,
+    // a request for the opposite of the heading becomes a U-turn, a left turn first and the
+    // reversed direction on the next cell
+    Queue,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TurnRequestPolicy {
+    // requests waiting for the snake to turn, the oldest ones are dropped first
+    pub depth: usize,
+    pub reversals: ReversalPolicy,
+}
+
+impl Default for TurnRequestPolicy {
+    fn default() -> Self {
+        Self {
+            depth: 2,
+            reversals: ReversalPolicy::Reject,
+        }
+    }
+}
+
+// Turn requests of one player, waiting for the snake to get to the next cell
+#[derive(Component)]
+pub struct TurnRequestsBuffer {
+    buffer: VecDeque<RequestDirection>,
+    pub policy: TurnRequestPolicy,
+}
+
+impl TurnRequestsBuffer {
+    // next turn for a snake going towards `heading`, requests which wouldn't turn it are skipped
+    pub fn pop(&mut self, heading: RequestDirection) -> Option<RequestDirection> {
+        while let Some(request) = self.buffer.pop_front() {
+            if request == heading {
+                continue;
+            }
+            if request == heading.opposite() {
+                match self.policy.reversals {
+                    ReversalPolicy::Reject => continue,
+                    ReversalPolicy::Queue => {
+                        self.buffer.push_front(request);
+                        return Some(heading.turned_left());
+                    }
+                }
+            }
+            return Some(request);
+        }
+        None
+    }
+
+    // repeating the last request doesn't buffer it again
+    pub fn push(&mut self, direction: RequestDirection) {
+        if self.buffer.back() == Some(&direction) {
+            return;
+        }
+        self.buffer.push_back(direction);
+        while self.buffer.len() > self.policy.depth {
+            self.buffer.pop_front();
+        }
+    }
+
+    pub fn with_policy(policy: TurnRequestPolicy) -> Self {
+        Self {
+            buffer: VecDeque::new(),
+            policy,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{InputBindings, ReversalPolicy, TurnRequestPolicy, TurnRequestsBuffer};
+    use crate::input::RequestDirection::{self, Down, Left, Right, Up};
+    use bevy::prelude::*;
+
+    #[test]
+    fn test_reject_reversal() {
+        let mut buffer = TurnRequestsBuffer::with_policy(TurnRequestPolicy::default());
+        buffer.push(Down);
+        assert_eq!(buffer.pop(Up), None);
+
+        buffer.push(Down);
+        buffer.push(Left);
+        assert_eq!(buffer.pop(Up), Some(Left));
+        assert_eq!(buffer.pop(Left), None);
+    }
+
+    #[test]
+    fn test_queue_reversal() {
+        let mut buffer = TurnRequestsBuffer::with_policy(TurnRequestPolicy {
+            reversals: ReversalPolicy::Queue,
+            ..TurnRequestPolicy::default()
+        });
+        buffer.push(Down);
+        assert_eq!(buffer.pop(Up), Some(Left));
+        assert_eq!(buffer.pop(Left), Some(Down));
+        assert_eq!(buffer.pop(Down), None);
+    }
+
+    #[test]
+    fn test_redundant_requests() {
+        let mut buffer = TurnRequestsBuffer::with_policy(TurnRequestPolicy::default());
+        buffer.push(Left);
+        buffer.push(Left);
+        buffer.push(Up);
+        assert_eq!(buffer.pop(Right), Some(Up));
+        assert_eq!(buffer.pop(Up), None);
+
+        // already going that way
+        buffer.push(Right);
+        assert_eq!(buffer.pop(Right), None);
+    }
+
+    #[test]
+    fn test_depth() {
+        let mut buffer = TurnRequestsBuffer::with_policy(TurnRequestPolicy {
+            depth: 3,
+            ..TurnRequestPolicy::default()
+        });
+        for request in [Up, Left, Down, Right] {
+            buffer.push(request);
+        }
+        assert_eq!(buffer.pop(Up), Some(Left));
+        assert_eq!(buffer.pop(Left), Some(Down));
+        assert_eq!(buffer.pop(Down), Some(Right));
+        assert_eq!(buffer.pop(Right), None);
+
+        buffer.policy.depth = 0;
+        buffer.push(Up);
+        assert_eq!(buffer.pop(Right), None);
+    }
+
+    #[test]
+    fn test_bindings() {
+        let mut keys = ButtonInput::<KeyCode>::default();
+        let mut gamepad_buttons = ButtonInput::<GamepadButton>::default();
+        keys.press(KeyCode::KeyW);
+        gamepad_buttons.press(GamepadButton::new(
+            Gamepad::new(1),
+            GamepadButtonType::DPadLeft,
+        ));
+        let pressed = |bindings: InputBindings| bindings.just_pressed(&keys, &gamepad_buttons);
+        assert_eq!(pressed(InputBindings::Arrows), None);
+        assert_eq!(pressed(InputBindings::Wasd), Some(RequestDirection::Up));
+        assert_eq!(pressed(InputBindings::Gamepad(0)), None);
+        assert_eq!(pressed(InputBindings::Gamepad(1)), Some(Left));
+    }
+}
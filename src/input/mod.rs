@@ -1,16 +1,15 @@
 mod components;
-mod resources;
 mod systems;
 
 use bevy::prelude::*;
 
-pub use components::RequestDirection;
-pub use resources::TurnRequestsBuffer;
+pub use components::{
+    InputBindings, RequestDirection, ReversalPolicy, TurnRequestPolicy, TurnRequestsBuffer,
+};
 pub struct InputPlugin;
 
 impl Plugin for InputPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Update, systems::handle_input)
-            .insert_resource(TurnRequestsBuffer::new());
+        app.add_systems(Update, systems::handle_input);
     }
 }
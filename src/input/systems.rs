@@ -1,25 +1,15 @@
 use bevy::prelude::*;
 
-use super::RequestDirection;
-use super::TurnRequestsBuffer;
+use super::{InputBindings, TurnRequestsBuffer};
 
 pub fn handle_input(
-    key: Res<ButtonInput<KeyCode>>,
-    mut direction_change_requests: ResMut<TurnRequestsBuffer>,
+    keys: Res<ButtonInput<KeyCode>>,
+    gamepad_buttons: Res<ButtonInput<GamepadButton>>,
+    mut players: Query<(&InputBindings, &mut TurnRequestsBuffer)>,
 ) {
-    let new_direction = if key.just_pressed(KeyCode::ArrowLeft) {
-        Some(RequestDirection::Left)
-    } else if key.just_pressed(KeyCode::ArrowRight) {
-        Some(RequestDirection::Right)
-    } else if key.just_pressed(KeyCode::ArrowUp) {
-        Some(RequestDirection::Up)
-    } else if key.just_pressed(KeyCode::ArrowDown) {
-        Some(RequestDirection::Down)
-    } else {
-        None
-    };
-
-    if let Some(direction) = new_direction {
-        direction_change_requests.push(direction);
+    for (bindings, mut direction_change_requests) in players.iter_mut() {
+        if let Some(direction) = bindings.just_pressed(&keys, &gamepad_buttons) {
+            direction_change_requests.push(direction);
+        }
     }
 }
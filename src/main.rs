@@ -1,17 +1,21 @@
 mod asset_loader;
 mod field;
+mod food;
 mod input;
 mod player;
 mod plugins;
 mod scene;
 // mod snake;
+mod snake_debug;
+mod snake_face;
 mod snake_mesh;
+mod snake_picking;
 mod states;
 use bevy::{pbr::PbrPlugin, prelude::*};
 
 use bevy_flycam::PlayerPlugin;
 use bevy_inspector_egui::quick::WorldInspectorPlugin;
-use snake_mesh::PolygonizationSettings;
+use snake_mesh::{PolygonizationSettings, MIN_GRID_RESOLUTION};
 
 pub fn setup(
     mut commands: Commands,
@@ -40,12 +44,18 @@ pub fn setup(
     commands.spawn((
         snake_mesh::SnakeMesh {
             radius: 5.0,
-            center: Vec3::new(0.0, 0.0, 0.0),
+            body: vec![Vec3::new(0.0, 0.0, 0.0)],
+            profile: snake_mesh::RadiusProfile::default(),
+            colors: snake_mesh::SnakeColors::default(),
+            eyes: None,
+            dissolve: None,
             fake_mesh_asset: meshes.add(Cuboid::default()).into(),
         },
         PolygonizationSettings {
             grid_size: Vec3::new(20.0, 20.0, 20.0),
             grid_origin: Vec3::new(-10.0, -10.0, -10.0),
+            resolution: MIN_GRID_RESOLUTION,
+            relaxation_iterations: 0,
         },
         materials.add(StandardMaterial {
             base_color: Color::ORANGE_RED,
@@ -58,6 +68,7 @@ pub fn setup(
             visibility: Visibility::Visible,
             ..default()
         },
+        snake_face::SnakeFace::default(),
     ));
 }
 
@@ -68,6 +79,7 @@ fn main() {
             // input::InputPlugin,
             // player::PlayerPlugin,
             // field::FieldPlugin,
+            // food::FoodPlugin,
             // asset_loader::AssetLoaderPlugin,
             DefaultPlugins
                 .set(WindowPlugin {
@@ -85,6 +97,9 @@ fn main() {
             WorldInspectorPlugin::new(),
             PlayerPlugin,
             snake_mesh::SnakeMeshPlugin,
+            snake_debug::SnakeDebugPlugin,
+            snake_face::SnakeFacePlugin,
+            snake_picking::SnakePickingPlugin,
         ))
         .add_systems(Startup, setup)
         .register_type::<snake_mesh::SnakeMesh>()
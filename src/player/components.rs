@@ -1,6 +1,7 @@
 use bevy::prelude::*;
 
-use std::f32::consts::PI;
+use std::collections::VecDeque;
+use std::f32::consts::{PI, TAU};
 
 use crate::input::RequestDirection;
 
@@ -10,6 +11,10 @@ pub struct Player;
 #[derive(Component)]
 pub struct Speed(pub f32);
 
+// Stays on a snake after it crashed, SnakeDeath is gone once the animation is over
+#[derive(Component)]
+pub struct Crashed;
+
 #[derive(Component)]
 pub struct TurnSpeed(pub f32);
 
@@ -24,6 +29,134 @@ pub struct BodyInfo {
     pub body: Vec<Entity>,
     pub first_gap: f32,
     pub gap: f32,
+    // 1.0 when the last fragment is a whole gap behind the one before it, less while it grows
+    pub tail_extension: f32,
+}
+
+impl BodyInfo {
+    // path behind the head needed for the body, including a fragment growing out of the tail
+    pub fn length(&self) -> f32 {
+        self.first_gap + self.body.len() as f32 * self.gap
+    }
+
+    // distance along the path from the head to the fragment
+    pub fn fragment_distance(&self, index: usize) -> f32 {
+        let distance = self.first_gap + index as f32 * self.gap;
+        if index + 1 == self.body.len() {
+            distance - (1.0 - self.tail_extension) * self.gap
+        } else {
+            distance
+        }
+    }
+}
+
+// Fragments waiting to be added at the tail, one at a time
+#[derive(Component, Copy, Clone, PartialEq, Debug)]
+pub struct Growth {
+    // fragments added for every PreySwallowed of size 1.0
+    pub per_food: u32,
+    // seconds for a new fragment to slide out of the tail
+    pub extend_duration: f32,
+    pub pending: u32,
+}
+
+impl Growth {
+    pub fn new(per_food: u32) -> Self {
+        Self {
+            per_food,
+            extend_duration: 0.15,
+            pending: 0,
+        }
+    }
+
+    pub fn grow(&mut self, fragments: u32) {
+        self.pending += fragments;
+    }
+
+    pub fn feed(&mut self, size: f32) {
+        self.grow((self.per_food as f32 * size).round() as u32);
+    }
+}
+
+// Visual side to side slithering of the snake mesh, fragments and the gameplay path stay as they
+// are. The wave is fixed to the ground, so it goes faster together with Speed.
+#[derive(Component, Copy, Clone, PartialEq, Debug)]
+pub struct Undulation {
+    pub amplitude: f32,
+    pub wavelength: f32,
+    // distance behind the head over which the amplitude ramps up, the head stays on the path
+    pub head_falloff: f32,
+}
+
+impl Default for Undulation {
+    fn default() -> Self {
+        Self {
+            amplitude: 0.15,
+            wavelength: 2.0,
+            head_falloff: 0.5,
+        }
+    }
+}
+
+impl Undulation {
+    // sideways offset of the body point `distance_from_head` behind a head that went `head_distance`
+    pub fn offset(&self, distance_from_head: f32, head_distance: f32) -> f32 {
+        let path_position = (head_distance - distance_from_head).max(0.0);
+        let ramp = (distance_from_head / self.head_falloff.max(f32::EPSILON)).clamp(0.0, 1.0);
+        self.amplitude * ramp * (TAU * path_position / self.wavelength.max(f32::EPSILON)).sin()
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Debug, Default)]
+pub enum MovementMode {
+    // continuous movement, turns are arcs started on MovedOntoNewCellEvent
+    #[default]
+    Smooth,
+    // exactly one Cell per tick like the classic game, turns happen on cell centers
+    Stepped,
+}
+
+// Head of a snake in MovementMode::Stepped, drawn in between the cell centers of the last tick
+#[derive(Component, Copy, Clone, PartialEq, Debug)]
+pub struct GridStep {
+    pub from: Transform,
+    pub to: Transform,
+    // 0.0 at `from`, the next tick happens at 1.0
+    pub progress: f32,
+}
+
+impl GridStep {
+    // the first tick happens right away
+    pub fn new(start: Transform) -> Self {
+        Self {
+            from: start,
+            to: start,
+            progress: 1.0,
+        }
+    }
+
+    // starts the next step from where the last one ended
+    pub fn advance(&mut self, direction: Direction, cell_size: f32) {
+        let rotation = direction.quaternion();
+        self.from = self.to;
+        self.to =
+            Transform::from_translation(self.from.translation + rotation * Vec3::Z * cell_size)
+                .with_rotation(rotation)
+                .with_scale(self.from.scale);
+    }
+
+    pub fn transform(&self) -> Transform {
+        let progress = self.progress.clamp(0.0, 1.0);
+        Transform {
+            translation: self.from.translation.lerp(self.to.translation, progress),
+            // the head is facing the new way before it reaches the next cell
+            rotation: self
+                .from
+                .rotation
+                .slerp(self.to.rotation, (progress * 2.0).min(1.0)),
+            scale: self.to.scale,
+        }
+    }
 }
 
 #[derive(Copy, Clone, PartialEq, Debug)]
@@ -32,8 +165,45 @@ pub struct PreviousHeadPosition {
     pub distance_passed: DistancePassed,
 }
 
+// Path of the head, oldest first, trimmed to what the body needs
 #[derive(Component)]
-pub struct PreviousHeadPositions(pub Vec<PreviousHeadPosition>);
+pub struct PreviousHeadPositions(VecDeque<PreviousHeadPosition>);
+
+impl PreviousHeadPositions {
+    pub fn new(start: PreviousHeadPosition) -> Self {
+        Self(VecDeque::from([start]))
+    }
+
+    // forgets positions more than `length` behind the new one, except one to interpolate from
+    pub fn push(&mut self, position: PreviousHeadPosition, length: f32) {
+        self.0.push_back(position);
+        let oldest_needed = position.distance_passed.0 - length;
+        while self.0.len() > 1 && self.0[1].distance_passed.0 <= oldest_needed {
+            self.0.pop_front();
+        }
+    }
+
+    // where the head was when it had passed `distance`, clamped to the known path
+    pub fn sample(&self, distance: f32) -> Option<Transform> {
+        let next = self
+            .0
+            .partition_point(|position| position.distance_passed.0 < distance);
+        if next == 0 {
+            return self.0.front().map(|position| position.transform);
+        }
+        if next == self.0.len() {
+            return self.0.back().map(|position| position.transform);
+        }
+        let (from, to) = (self.0[next - 1], self.0[next]);
+        let t =
+            (distance - from.distance_passed.0) / (to.distance_passed.0 - from.distance_passed.0);
+        Some(Transform {
+            translation: from.transform.translation.lerp(to.transform.translation, t),
+            rotation: from.transform.rotation.slerp(to.transform.rotation, t),
+            scale: from.transform.scale.lerp(to.transform.scale, t),
+        })
+    }
+}
 
 pub enum TurnDirection {
     Left,
@@ -41,6 +211,7 @@ pub enum TurnDirection {
 }
 
 impl TurnDirection {
+    // reversals are up to the TurnRequestsBuffer policy, they aren't a single turn
     pub fn from_turn_request(
         current: Direction,
         request: RequestDirection,
@@ -49,25 +220,21 @@ impl TurnDirection {
             Direction::Left => match request {
                 RequestDirection::Up => Some(TurnDirection::Left),
                 RequestDirection::Down => Some(TurnDirection::Right),
-                RequestDirection::Right => Some(TurnDirection::Right),
                 _ => None,
             },
             Direction::Right => match request {
                 RequestDirection::Up => Some(TurnDirection::Right),
                 RequestDirection::Down => Some(TurnDirection::Left),
-                RequestDirection::Left => Some(TurnDirection::Left),
                 _ => None,
             },
             Direction::Up => match request {
                 RequestDirection::Left => Some(TurnDirection::Right),
                 RequestDirection::Right => Some(TurnDirection::Left),
-                RequestDirection::Down => Some(TurnDirection::Right),
                 _ => None,
             },
             Direction::Down => match request {
                 RequestDirection::Left => Some(TurnDirection::Left),
                 RequestDirection::Right => Some(TurnDirection::Right),
-                RequestDirection::Up => Some(TurnDirection::Left),
                 _ => None,
             },
         }
@@ -83,7 +250,78 @@ impl TurnDirection {
 
 pub struct TurningValue {
     pub direction: TurnDirection,
+    // angle turned so far, negative while the head still goes straight to the start of the arc
     pub progress: f32,
+    // on the lane the snake is leaving
+    pub start: Transform,
+}
+
+impl TurningValue {
+    // the arc ends on the lane through `cell_center`, so it's centered in the cell
+    pub fn centered(
+        direction: TurnDirection,
+        head: &Transform,
+        heading: Direction,
+        cell_center: Vec3,
+        radius: f32,
+    ) -> Self {
+        let rotation = heading.quaternion();
+        let forward = rotation * Vec3::Z;
+        // the head is only on the lane up to float errors
+        let lane_point = cell_center + forward * forward.dot(head.translation - cell_center);
+        let start = Transform {
+            translation: cell_center - forward * radius,
+            rotation,
+            scale: head.scale,
+        };
+        let progress = forward.dot(lane_point - start.translation) / radius;
+        Self {
+            direction,
+            progress,
+            start,
+        }
+    }
+
+    pub fn transform(&self, radius: f32) -> Transform {
+        let forward = self.start.rotation * Vec3::Z;
+        if self.progress < 0.0 {
+            return self
+                .start
+                .with_translation(self.start.translation + forward * self.progress * radius);
+        }
+        let angle = self.progress.min(PI / 2.0);
+        let rotation =
+            self.start.rotation * Quat::from_axis_angle(Vec3::Y, angle * self.direction.sign());
+        let side = self.start.rotation
+            * Quat::from_axis_angle(Vec3::Y, PI / 2.0 * self.direction.sign())
+            * Vec3::Z;
+        self.start
+            .with_translation(
+                self.start.translation
+                    + forward * radius * angle.sin()
+                    + side * radius * (1.0 - angle.cos()),
+            )
+            .with_rotation(rotation)
+    }
+
+    // moves the head along the arc, returns the new direction once the turn is done
+    pub fn advance(
+        &mut self,
+        distance: f32,
+        radius: f32,
+        transform: &mut Transform,
+    ) -> Option<Direction> {
+        self.progress += distance / radius;
+        *transform = self.transform(radius);
+        if self.progress < PI / 2.0 {
+            return None;
+        }
+        // snapped to the new lane, with what's left of the distance after the arc
+        let direction = Direction::closest_from_rotation(&transform.rotation);
+        transform.rotation = direction.quaternion();
+        transform.translation += transform.rotation * Vec3::Z * (self.progress - PI / 2.0) * radius;
+        Some(direction)
+    }
 }
 
 #[derive(Component)]
@@ -130,10 +368,37 @@ impl Direction {
     }
 }
 
+impl From<Direction> for RequestDirection {
+    fn from(direction: Direction) -> Self {
+        match direction {
+            Direction::Left => RequestDirection::Left,
+            Direction::Right => RequestDirection::Right,
+            Direction::Up => RequestDirection::Up,
+            Direction::Down => RequestDirection::Down,
+        }
+    }
+}
+
+impl From<RequestDirection> for Direction {
+    fn from(request: RequestDirection) -> Self {
+        match request {
+            RequestDirection::Left => Direction::Left,
+            RequestDirection::Right => Direction::Right,
+            RequestDirection::Up => Direction::Up,
+            RequestDirection::Down => Direction::Down,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::Direction;
+    use super::{
+        BodyInfo, Direction, DistancePassed, GridStep, PreviousHeadPosition, PreviousHeadPositions,
+        TurnDirection, TurningValue, Undulation,
+    };
+    use crate::field::{Cell, Field};
     use bevy::prelude::*;
+    use rand::{rngs::StdRng, Rng, SeedableRng};
     use std::f32::consts::PI;
 
     #[test]
@@ -170,4 +435,140 @@ mod tests {
             assert!(quat.abs_diff_eq(expected_quat, 1e-6));
         }
     }
+
+    #[test]
+    fn test_fragment_distance() {
+        let mut body_info = BodyInfo {
+            body: vec![Entity::PLACEHOLDER; 3],
+            first_gap: 0.5,
+            gap: 0.25,
+            tail_extension: 1.0,
+        };
+        assert_eq!(body_info.fragment_distance(0), 0.5);
+        assert_eq!(body_info.fragment_distance(2), 1.0);
+        body_info.tail_extension = 0.0;
+        assert_eq!(body_info.fragment_distance(1), 0.75);
+        assert_eq!(body_info.fragment_distance(2), 0.75);
+    }
+
+    #[test]
+    fn test_undulation_offset() {
+        let undulation = Undulation {
+            amplitude: 0.5,
+            wavelength: 4.0,
+            head_falloff: 1.0,
+        };
+        // head stays on the path
+        assert_eq!(undulation.offset(0.0, 3.0), 0.0);
+        // fragments which haven't left the start yet
+        assert_eq!(undulation.offset(5.0, 3.0), 0.0);
+        assert!((undulation.offset(2.0, 3.0) - 0.5).abs() < 1e-6);
+        assert!((undulation.offset(0.5, 1.5) - 0.25).abs() < 1e-6);
+        // the same point on the ground keeps its offset while the snake moves
+        assert!((undulation.offset(2.0, 5.0) - undulation.offset(4.0, 7.0)).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_grid_step() {
+        let mut step = GridStep::new(Transform::from_xyz(0.5, 0.5, 0.5));
+        step.advance(Direction::Right, 1.0);
+        step.progress = 0.5;
+        let forward = Direction::Right.quaternion() * Vec3::Z;
+        assert!(step
+            .transform()
+            .translation
+            .abs_diff_eq(Vec3::splat(0.5) + forward * 0.5, 1e-6));
+
+        step.advance(Direction::Up, 1.0);
+        let up = Direction::Up.quaternion() * Vec3::Z;
+        assert!(step
+            .to
+            .translation
+            .abs_diff_eq(step.from.translation + up, 1e-6));
+        step.progress = 1.0;
+        assert_eq!(step.transform().translation, step.to.translation);
+        assert!(step
+            .transform()
+            .rotation
+            .abs_diff_eq(Direction::Up.quaternion(), 1e-6));
+    }
+
+    #[test]
+    fn test_turns_stay_on_lanes() {
+        let field = Field::new(IVec2::new(10, 10), Vec2::new(10.0, 10.0), Vec2::ZERO);
+        let mut rng = StdRng::seed_from_u64(3);
+        let speed = 3.0;
+        let radius = field.cell_size() / 2.0;
+        let delta = 1.0 / 64.0;
+        // distance of the head from the lane through the centers of its cells
+        let lane_offset = |head: &Transform, direction: Direction| {
+            let center = field.translation_of_cell(&field.cell(head.translation.xz()));
+            let side = (direction.quaternion() * Vec3::X).xz();
+            side.dot(head.translation.xz() - center).abs()
+        };
+
+        let mut cell = Cell::new(4, 4);
+        let start = field.translation_of_cell(&cell);
+        let mut head = Transform::from_xyz(start.x, 0.5, start.y);
+        let mut direction = Direction::Right;
+        let mut turning: Option<TurningValue> = None;
+        let mut turns = 0;
+        while turns < 100 {
+            if let Some(turning_value) = turning.as_mut() {
+                if let Some(new_direction) = turning_value.advance(speed * delta, radius, &mut head)
+                {
+                    direction = new_direction;
+                    turning = None;
+                    turns += 1;
+                    assert!(lane_offset(&head, direction) < 1e-3);
+                }
+            } else {
+                head.translation += head.rotation * Vec3::Z * speed * delta;
+            }
+            let current = field.cell(head.translation.xz());
+            if current == cell {
+                continue;
+            }
+            cell = current;
+            if turning.is_none() && rng.gen_bool(0.5) {
+                let turn = if rng.gen() {
+                    TurnDirection::Left
+                } else {
+                    TurnDirection::Right
+                };
+                let center = field.translation_of_cell(&cell);
+                let center = Vec3::new(center.x, head.translation.y, center.y);
+                turning = Some(TurningValue::centered(
+                    turn, &head, direction, center, radius,
+                ));
+            }
+        }
+        assert!(lane_offset(&head, direction) < 1e-3);
+    }
+
+    #[test]
+    fn test_previous_head_positions() {
+        let position = |distance: f32| PreviousHeadPosition {
+            transform: Transform::from_xyz(distance, 0.0, 0.0),
+            distance_passed: DistancePassed(distance),
+        };
+        let mut positions = PreviousHeadPositions::new(position(0.0));
+        assert_eq!(positions.sample(-1.0), Some(position(0.0).transform));
+        for i in 1..=40 {
+            positions.push(position(i as f32 * 0.25), 1.0);
+        }
+        // the oldest one is exactly as far back as the length, to interpolate from
+        let distances: Vec<f32> = positions
+            .0
+            .iter()
+            .map(|position| position.distance_passed.0)
+            .collect();
+        assert_eq!(distances, vec![9.0, 9.25, 9.5, 9.75, 10.0]);
+
+        let sampled = positions.sample(9.1).unwrap();
+        assert!((sampled.translation.x - 9.1).abs() < 1e-5);
+        // clamped to the known path
+        assert_eq!(positions.sample(0.0), Some(position(9.0).transform));
+        assert_eq!(positions.sample(20.0), Some(position(10.0).transform));
+    }
 }
@@ -1,4 +1,33 @@
 use bevy::prelude::*;
 
-#[derive(Event)]
-pub struct MovedOntoNewCellEvent;
+use crate::field::Cell;
+
+#[derive(Event, Clone, Copy, Debug)]
+pub struct MovedOntoNewCellEvent {
+    pub snake: Entity,
+    pub cell: Cell,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CollisionKind {
+    // a fragment of its own or another snake's body
+    Body(Entity),
+    // left the field
+    Wall,
+    Obstacle(Entity),
+}
+
+// Sent once when the head of a snake crashes, the snake stops and plays its death animation
+#[derive(Event, Clone, Copy, Debug)]
+pub struct CollisionEvent {
+    pub snake: Entity,
+    pub kind: CollisionKind,
+}
+
+// Sent when a new tail fragment has fully slid out
+#[derive(Event, Clone, Copy, Debug)]
+pub struct SnakeGrew {
+    pub snake: Entity,
+    // fragments in BodyInfo::body
+    pub length: usize,
+}
@@ -5,9 +5,17 @@ mod systems;
 
 use bevy::prelude::*;
 
-use systems::{check_if_on_new_cell, handle_input, move_body, move_head, setup};
+use systems::{
+    check_collisions, check_if_on_new_cell, crash_snakes, enter_game_over, feed_snakes,
+    grow_snakes, handle_input, move_body, move_head, setup, step_head, update_snake_mesh,
+};
 
-use crate::field::Cell;
+pub use components::{Fragment, Player};
+pub use events::MovedOntoNewCellEvent;
+
+use crate::field::{Cell, OccupancySet};
+use crate::input::{InputBindings, ReversalPolicy, TurnRequestPolicy};
+use crate::snake_mesh::SnakeDeathSet;
 use crate::states::GameState;
 
 pub struct PlayerPlugin;
@@ -17,19 +25,54 @@ impl Plugin for PlayerPlugin {
             .add_systems(
                 FixedUpdate,
                 (
-                    move_head,
-                    move_body.after(move_head),
-                    check_if_on_new_cell,
+                    move_head.before(OccupancySet),
+                    step_head.before(OccupancySet),
+                    grow_snakes.before(move_body),
+                    move_body
+                        .after(move_head)
+                        .after(step_head)
+                        .before(OccupancySet),
+                    update_snake_mesh.after(move_body),
+                    (check_collisions, crash_snakes).chain().after(OccupancySet),
+                    check_if_on_new_cell.before(OccupancySet),
                     handle_input.after(check_if_on_new_cell),
                 )
                     .run_if(in_state(GameState::InGame)),
             )
+            // events could be missed by frames without a fixed update
+            .add_systems(
+                Update,
+                (feed_snakes, enter_game_over.after(SnakeDeathSet))
+                    .run_if(in_state(GameState::InGame)),
+            )
             .add_event::<events::MovedOntoNewCellEvent>()
+            .add_event::<events::SnakeGrew>()
+            .add_event::<events::CollisionEvent>()
             .insert_resource(resources::PlayerStartSetting {
-                cell: Cell::new(4, 4),
-                direction: components::Direction::Right,
+                players: vec![
+                    resources::PlayerSpawn {
+                        cell: Cell::new(4, 4),
+                        direction: components::Direction::Right,
+                        bindings: InputBindings::Arrows,
+                        color: Color::DARK_GREEN,
+                    },
+                    resources::PlayerSpawn {
+                        cell: Cell::new(7, 5),
+                        direction: components::Direction::Left,
+                        bindings: InputBindings::Wasd,
+                        color: Color::MAROON,
+                    },
+                ],
+                turn_requests: TurnRequestPolicy {
+                    depth: 2,
+                    reversals: ReversalPolicy::Reject,
+                },
                 speed: 3.0,
+                movement: components::MovementMode::Smooth,
                 gap: 0.1,
+                undulation: Some(components::Undulation::default()),
+                growth_per_food: 3,
+                death_duration: 1.5,
             });
     }
 }
@@ -1,12 +1,26 @@
-use super::components::Direction;
+use super::components::{Direction, MovementMode, Undulation};
 use crate::field::Cell;
+use crate::input::{InputBindings, TurnRequestPolicy};
 
 use bevy::prelude::*;
 
-#[derive(Resource)]
-pub struct PlayerStartSetting {
+// One snake for every player, steered with its own bindings
+pub struct PlayerSpawn {
     pub cell: Cell,
     pub direction: Direction,
+    pub bindings: InputBindings,
+    pub color: Color,
+}
+
+#[derive(Resource)]
+pub struct PlayerStartSetting {
+    pub players: Vec<PlayerSpawn>,
+    pub turn_requests: TurnRequestPolicy,
     pub speed: f32,
+    pub movement: MovementMode,
     pub gap: f32,
+    pub undulation: Option<Undulation>,
+    pub growth_per_food: u32,
+    // seconds the snake takes to deflate after crashing
+    pub death_duration: f32,
 }
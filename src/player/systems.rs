@@ -1,16 +1,21 @@
 use std::f32::consts::PI;
 
 use super::components::{
-    BodyInfo, DistancePassed, Fragment, PreviousHeadPosition, PreviousHeadPositions, TurnDirection,
-    Turning, TurningValue,
+    BodyInfo, Crashed, DistancePassed, Fragment, GridStep, Growth, MovementMode,
+    PreviousHeadPosition, PreviousHeadPositions, TurnDirection, Turning, TurningValue, Undulation,
 };
 use super::components::{Direction, Player, Speed, TurnSpeed};
-use super::events::MovedOntoNewCellEvent;
+use super::events::{CollisionEvent, CollisionKind, MovedOntoNewCellEvent, SnakeGrew};
 use super::resources::PlayerStartSetting;
 
-use crate::asset_loader::{AssetsStorage, SceneAssets};
-use crate::field::{Cell, Field};
+use crate::field::{Cell, Field, Occupant, OccupantKind};
 use crate::input::TurnRequestsBuffer;
+use crate::snake_face::SnakeFace;
+use crate::snake_mesh::{
+    DeathStyle, PolygonizationSettings, PreySwallowed, RadiusProfile, SnakeColors, SnakeDeath,
+    SnakeDied, SnakeMesh, MAX_BODY_POINTS,
+};
+use crate::states::GameState;
 
 use bevy::prelude::*;
 
@@ -18,74 +23,86 @@ pub fn setup(
     mut commands: Commands,
     start_settings: Res<PlayerStartSetting>,
     field: Res<Field>,
-    assets_storage: Res<AssetsStorage>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
 ) {
     info!("snake setup");
-    let cell_coordinates = field.translation_of_cell(&start_settings.cell);
-    let head_translation = Vec3::new(cell_coordinates.x, 0.5, cell_coordinates.y);
     let turn_moment = 0.0;
-    let scaled_speed = start_settings.speed * field.cell_size();
     let cell_part_for_turn = 1.0 - (turn_moment * 2.0);
-    let turn_speed = scaled_speed * 2.0 / cell_part_for_turn;
-
-    let scaling = 0.4;
-    let start_transform =
-        Transform::from_translation(head_translation).with_scale(Vec3::splat(scaling));
-
-    let meshes = [
-        assets_storage.handles[&SceneAssets::SnakeHead7].clone(),
-        assets_storage.handles[&SceneAssets::SnakeHead6].clone(),
-        assets_storage.handles[&SceneAssets::SnakeHead5].clone(),
-        assets_storage.handles[&SceneAssets::SnakeHead4].clone(),
-        assets_storage.handles[&SceneAssets::SnakeHead3].clone(),
-        assets_storage.handles[&SceneAssets::SnakeHead2].clone(),
-        assets_storage.handles[&SceneAssets::SnakeHead1].clone(),
-    ];
-
-    let mut body_list = Vec::<Entity>::new();
-    for fragment_part in 0..50 {
-        let scene = if fragment_part < meshes.len() {
-            meshes[fragment_part].clone()
-        } else {
-            assets_storage.handles[&SceneAssets::SnakeMainBody].clone()
+    // angle per distance, the arc has a radius of half the cell part
+    let turn_speed = start_settings.speed * 2.0 / (field.cell_size() * cell_part_for_turn);
+
+    for spawn in start_settings.players.iter() {
+        let cell_coordinates = field.translation_of_cell(&spawn.cell);
+        let head_translation = Vec3::new(cell_coordinates.x, 0.5, cell_coordinates.y);
+        let start_transform = Transform::from_translation(head_translation)
+            .with_rotation(spawn.direction.quaternion());
+
+        // fragments only mark where the body goes, the whole snake is drawn by the SnakeMesh
+        let mut body_list = Vec::<Entity>::new();
+        for fragment_part in 0..50 {
+            let id = commands
+                .spawn((
+                    TransformBundle::from_transform(start_transform),
+                    DistancePassed(0.0),
+                    Fragment(fragment_part as u32),
+                    Occupant(OccupantKind::SnakeBody),
+                ))
+                .id();
+            body_list.push(id);
+        }
+        let snake_mesh = SnakeMesh {
+            radius: field.cell_size() * 0.25,
+            body: vec![Vec3::ZERO],
+            profile: RadiusProfile::snake(),
+            colors: SnakeColors::default(),
+            eyes: None,
+            dissolve: None,
+            fake_mesh_asset: meshes.add(Cuboid::default()).into(),
         };
-        let id = commands
-            .spawn((
-                SceneBundle {
-                    scene,
-                    // visibility: Visibility::Hidden,
-                    transform: start_transform,
-                    ..default()
-                },
-                DistancePassed(0.0),
-                Fragment(fragment_part as u32),
-            ))
-            .id();
-        body_list.push(id);
-    }
-    commands.spawn((
-        SceneBundle {
-            scene: assets_storage.handles[&SceneAssets::SnakeHead8].clone(),
-            transform: start_transform,
-            ..default()
-        },
-        Player,
-        Speed(start_settings.speed),
-        start_settings.direction,
-        start_settings.cell,
-        TurnSpeed(turn_speed),
-        Turning(None),
-        PreviousHeadPositions(vec![PreviousHeadPosition {
-            transform: Transform::from_translation(head_translation),
-            distance_passed: DistancePassed(0.0),
-        }]),
-        DistancePassed(0.0),
-        BodyInfo {
-            body: body_list,
-            first_gap: start_settings.gap,
-            gap: start_settings.gap,
-        },
-    ));
+        let mut player = commands.spawn((
+            SpatialBundle::from_transform(start_transform),
+            PolygonizationSettings {
+                relaxation_iterations: 2,
+                ..PolygonizationSettings::enclosing(&snake_mesh)
+            },
+            snake_mesh,
+            materials.add(StandardMaterial {
+                base_color: spawn.color,
+                ..default()
+            }),
+            SnakeFace::default(),
+            Player,
+            Speed(start_settings.speed),
+            spawn.direction,
+            spawn.cell,
+            Occupant(OccupantKind::SnakeHead),
+            TurnSpeed(turn_speed),
+            Turning(None),
+            PreviousHeadPositions::new(PreviousHeadPosition {
+                transform: start_transform,
+                distance_passed: DistancePassed(0.0),
+            }),
+            DistancePassed(0.0),
+        ));
+        player.insert((
+            BodyInfo {
+                body: body_list,
+                first_gap: start_settings.gap,
+                gap: start_settings.gap,
+                tail_extension: 1.0,
+            },
+            Growth::new(start_settings.growth_per_food),
+            spawn.bindings,
+            TurnRequestsBuffer::with_policy(start_settings.turn_requests),
+        ));
+        if let Some(undulation) = start_settings.undulation {
+            player.insert(undulation);
+        }
+        if start_settings.movement == MovementMode::Stepped {
+            player.insert(GridStep::new(start_transform));
+        }
+    }
 }
 
 pub fn move_head(
@@ -99,8 +116,14 @@ pub fn move_head(
             &mut DistancePassed,
             &Speed,
             &TurnSpeed,
+            &BodyInfo,
+        ),
+        (
+            With<Player>,
+            Without<SnakeDeath>,
+            Without<Crashed>,
+            Without<GridStep>,
         ),
-        With<Player>,
     >,
 ) {
     for (
@@ -111,28 +134,86 @@ pub fn move_head(
         mut distance_passed,
         speed,
         turn_speed,
+        body_info,
     ) in transform_query.iter_mut()
     {
-        if let Some(turning_unwrapped) = turning.0.as_mut() {
-            let turn_delta = turn_speed.0 * time.delta_seconds();
-            turning_unwrapped.progress += turn_delta;
-            if turning_unwrapped.progress >= PI / 2.0 {
-                *direction = Direction::closest_from_rotation(&transform.rotation);
-                transform.rotation = direction.quaternion();
+        let distance = time.delta_seconds() * speed.0;
+        let radius = speed.0 / turn_speed.0;
+        if let Some(turning_value) = turning.0.as_mut() {
+            if let Some(new_direction) = turning_value.advance(distance, radius, &mut transform) {
+                *direction = new_direction;
                 turning.0 = None;
-            } else {
-                transform.rotation *=
-                    Quat::from_axis_angle(Vec3::Y, turn_delta * turning_unwrapped.direction.sign());
             }
+        } else {
+            let forward = transform.rotation * Vec3::Z;
+            transform.translation += forward * distance;
         }
-        let forward = transform.rotation * Vec3::Z;
+        distance_passed.0 += distance;
+        previous_transforms.push(
+            PreviousHeadPosition {
+                transform: transform.to_owned(),
+                distance_passed: distance_passed.to_owned(),
+            },
+            body_info.length(),
+        );
+    }
+}
+
+// Speed stays in distance per second, so a tick takes as long as crossing a cell in smooth mode
+pub fn step_head(
+    time: Res<Time>,
+    field: Res<Field>,
+    mut head_query: Query<
+        (
+            Entity,
+            &mut Transform,
+            &mut GridStep,
+            &mut Direction,
+            &mut Cell,
+            &mut PreviousHeadPositions,
+            &mut DistancePassed,
+            &Speed,
+            &BodyInfo,
+            &mut TurnRequestsBuffer,
+        ),
+        (With<Player>, Without<SnakeDeath>, Without<Crashed>),
+    >,
+    mut events: EventWriter<MovedOntoNewCellEvent>,
+) {
+    for (
+        snake,
+        mut transform,
+        mut grid_step,
+        mut direction,
+        mut cell,
+        mut previous_transforms,
+        mut distance_passed,
+        speed,
+        body_info,
+        mut input,
+    ) in head_query.iter_mut()
+    {
         let distance = time.delta_seconds() * speed.0;
-        transform.translation += forward * distance;
+        grid_step.progress += distance / field.cell_size();
+        while grid_step.progress >= 1.0 {
+            grid_step.progress -= 1.0;
+            if let Some(request) = input.pop((*direction).into()) {
+                *direction = request.into();
+            }
+            grid_step.advance(*direction, field.cell_size());
+            // the snake is in the next cell as soon as the tick happens
+            *cell = field.cell(grid_step.to.translation.xz());
+            events.send(MovedOntoNewCellEvent { snake, cell: *cell });
+        }
+        *transform = grid_step.transform();
         distance_passed.0 += distance;
-        previous_transforms.0.push(PreviousHeadPosition {
-            transform: transform.to_owned(),
-            distance_passed: distance_passed.to_owned(),
-        });
+        previous_transforms.push(
+            PreviousHeadPosition {
+                transform: transform.to_owned(),
+                distance_passed: distance_passed.to_owned(),
+            },
+            body_info.length(),
+        );
     }
 }
 
@@ -146,70 +227,335 @@ pub fn move_body(
     for (previous_head_positions, body_info, head_distance_passed) in
         previous_transforms_query.iter()
     {
-        let mut next_fragment_distance = body_info.first_gap;
-        let mut fragment_pointer: usize = 0;
-        let last = previous_head_positions.0.len() - 1;
-        for fragment_id in body_info.body.iter() {
-            for i in fragment_pointer..last {
-                let previous_head = previous_head_positions.0[last - i];
-                if head_distance_passed.0 - previous_head.distance_passed.0
-                    >= next_fragment_distance
-                {
-                    fragment_pointer = i;
-                    break;
-                }
+        for (fragment_index, fragment_id) in body_info.body.iter().enumerate() {
+            let fragment_distance = body_info.fragment_distance(fragment_index);
+            let Some(transform) =
+                previous_head_positions.sample(head_distance_passed.0 - fragment_distance)
+            else {
+                continue;
+            };
+            if let Ok(mut fragment_transform) = fragment_query.get_mut(*fragment_id) {
+                *fragment_transform = transform;
             }
-            let mut fragment_transform = fragment_query.get_mut(*fragment_id).unwrap();
-            *fragment_transform = previous_head_positions.0[last - fragment_pointer].transform;
-            next_fragment_distance += body_info.gap;
         }
     }
 }
 
+pub fn feed_snakes(mut events: EventReader<PreySwallowed>, mut snakes: Query<&mut Growth>) {
+    for event in events.read() {
+        if let Ok(mut growth) = snakes.get_mut(event.snake) {
+            growth.feed(event.size);
+        }
+    }
+}
+
+// new fragments start on top of the tail and slide out instead of popping in a gap behind it
+pub fn grow_snakes(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut snakes: Query<(Entity, &mut BodyInfo, &mut Growth), With<Player>>,
+    fragment_query: Query<&Transform, With<Fragment>>,
+    mut events: EventWriter<SnakeGrew>,
+) {
+    for (entity, mut body_info, mut growth) in snakes.iter_mut() {
+        if body_info.tail_extension >= 1.0 {
+            // the head is a body point too, longer snakes would be cut off by the sdf
+            if body_info.body.len() >= MAX_BODY_POINTS - 1 {
+                growth.pending = 0;
+            }
+            if growth.pending == 0 {
+                continue;
+            }
+            growth.pending -= 1;
+            let tail = body_info
+                .body
+                .last()
+                .and_then(|tail| fragment_query.get(*tail).ok())
+                .copied()
+                .unwrap_or_default();
+            let fragment = commands
+                .spawn((
+                    TransformBundle::from_transform(tail),
+                    DistancePassed(0.0),
+                    Fragment(body_info.body.len() as u32),
+                    Occupant(OccupantKind::SnakeBody),
+                ))
+                .id();
+            body_info.body.push(fragment);
+            body_info.tail_extension = 0.0;
+        }
+        body_info.tail_extension = (body_info.tail_extension
+            + time.delta_seconds() / growth.extend_duration.max(f32::EPSILON))
+        .min(1.0);
+        if body_info.tail_extension >= 1.0 {
+            events.send(SnakeGrew {
+                snake: entity,
+                length: body_info.body.len(),
+            });
+        }
+    }
+}
+
+// the snake mesh is in the head's local space
+pub fn update_snake_mesh(
+    mut player_query: Query<
+        (
+            &Transform,
+            &BodyInfo,
+            &DistancePassed,
+            Option<&Undulation>,
+            &mut SnakeMesh,
+            &mut PolygonizationSettings,
+        ),
+        With<Player>,
+    >,
+    fragment_query: Query<&Transform, (With<Fragment>, Without<Player>)>,
+) {
+    for (transform, body_info, distance_passed, undulation, mut snake_mesh, mut polygonization) in
+        player_query.iter_mut()
+    {
+        let to_local = transform.compute_affine().inverse();
+        let fragments = body_info
+            .body
+            .iter()
+            .filter_map(|fragment| fragment_query.get(*fragment).ok())
+            .enumerate()
+            .map(|(i, fragment_transform)| {
+                let Some(undulation) = undulation else {
+                    return fragment_transform.translation;
+                };
+                let distance_from_head = body_info.fragment_distance(i);
+                let side = fragment_transform.rotation * Vec3::X;
+                fragment_transform.translation
+                    + side * undulation.offset(distance_from_head, distance_passed.0)
+            });
+        snake_mesh.body = std::iter::once(transform.translation)
+            .chain(fragments)
+            .map(|point| to_local.transform_point3(point))
+            .collect();
+        *polygonization = PolygonizationSettings {
+            relaxation_iterations: polygonization.relaxation_iterations,
+            ..PolygonizationSettings::enclosing(&snake_mesh)
+        };
+    }
+}
+
+// turns are requested when the head enters a cell and scheduled to be centered in it
 pub fn handle_input(
-    mut turning_query: Query<(&mut Turning, &Transform, &Direction), With<Player>>,
-    mut input: ResMut<TurnRequestsBuffer>,
+    mut turning_query: Query<
+        (
+            Entity,
+            &mut Turning,
+            &Transform,
+            &Direction,
+            &Speed,
+            &TurnSpeed,
+            &mut TurnRequestsBuffer,
+        ),
+        (With<Player>, Without<GridStep>),
+    >,
     mut new_cell_events: EventReader<MovedOntoNewCellEvent>,
-    // field: Res<Field>,
+    field: Res<Field>,
 ) {
-    for (mut turning, _, direction) in turning_query.iter_mut() {
+    let new_cell_events: Vec<_> = new_cell_events.read().collect();
+    for (snake, mut turning, transform, direction, speed, turn_speed, mut input) in
+        turning_query.iter_mut()
+    {
         if turning.0.is_some() {
             continue;
         }
-        // let cell_local_translation = field.cell_local_translation(transform.translation.xz());
-        // let cell_size = field.cell_size();
-        // let cell_progress = match direction {
-        //     Direction::Left => (cell_size - cell_local_translation.x) / cell_size,
-        //     Direction::Right => cell_local_translation.x / cell_size,
-        //     Direction::Up => cell_local_translation.y / cell_size,
-        //     Direction::Down => (cell_size - cell_local_translation.y) / cell_size,
-        // };
-
-        for _ in new_cell_events.read() {
-            if let Some(turn_request) = input.pop() {
+        for new_cell in new_cell_events.iter().filter(|event| event.snake == snake) {
+            if let Some(turn_request) = input.pop((*direction).into()) {
                 if let Some(new_direction) =
                     TurnDirection::from_turn_request(*direction, turn_request)
                 {
-                    turning.0 = Some(TurningValue {
-                        direction: new_direction,
-                        progress: 0.0,
-                    });
+                    let center = field.translation_of_cell(&new_cell.cell);
+                    turning.0 = Some(TurningValue::centered(
+                        new_direction,
+                        transform,
+                        *direction,
+                        Vec3::new(center.x, transform.translation.y, center.y),
+                        speed.0 / turn_speed.0,
+                    ));
                 }
             }
         }
     }
 }
 
+// head against the field bounds, obstacles and the bodies of all snakes
+pub fn check_collisions(
+    field: Res<Field>,
+    heads: Query<
+        (Entity, &Transform, &SnakeMesh, &BodyInfo),
+        (With<Player>, Without<SnakeDeath>, Without<Crashed>),
+    >,
+    fragment_query: Query<(&Transform, &Fragment), Without<Player>>,
+    mut events: EventWriter<CollisionEvent>,
+) {
+    for (snake, transform, snake_mesh, body_info) in heads.iter() {
+        let head = transform.translation;
+        let cell = field.cell(head.xz());
+        // slightly less than two radii so bodies have to overlap
+        let reach = snake_mesh.radius * 1.6;
+        // turns are too wide for the body right behind the head to curl back into reach
+        let neck = reach * PI / 2.0;
+
+        let obstacle = field
+            .occupants(&cell)
+            .iter()
+            .find(|occupant| occupant.kind == OccupantKind::Wall)
+            .map(|occupant| CollisionKind::Obstacle(occupant.entity));
+        // fragments within reach can only be in the cells around the head
+        let body = || {
+            (-1..=1)
+                .flat_map(|x| (-1..=1).map(move |y| Cell::new(cell.i() + x, cell.j() + y)))
+                .flat_map(|neighbor| field.occupants(&neighbor).iter())
+                .filter(|occupant| occupant.kind == OccupantKind::SnakeBody)
+                .find(|occupant| {
+                    fragment_query
+                        .get(occupant.entity)
+                        .is_ok_and(|(fragment, Fragment(index))| {
+                            let own = body_info.body.get(*index as usize) == Some(&occupant.entity);
+                            (!own || body_info.fragment_distance(*index as usize) > neck)
+                                && fragment.translation.distance(head) < reach
+                        })
+                })
+                .map(|occupant| CollisionKind::Body(occupant.entity))
+        };
+        let kind = if field.contains(&cell) {
+            obstacle.or_else(body)
+        } else {
+            Some(CollisionKind::Wall)
+        };
+        if let Some(kind) = kind {
+            events.send(CollisionEvent { snake, kind });
+        }
+    }
+}
+
+pub fn crash_snakes(
+    mut commands: Commands,
+    start_settings: Res<PlayerStartSetting>,
+    mut events: EventReader<CollisionEvent>,
+) {
+    for event in events.read() {
+        commands.entity(event.snake).insert((
+            Crashed,
+            SnakeDeath::new(DeathStyle::Deflate, start_settings.death_duration),
+        ));
+    }
+}
+
+// waits for the death animation to finish, the game goes on while any other snake is left
+pub fn enter_game_over(
+    mut commands: Commands,
+    mut events: EventReader<SnakeDied>,
+    players: Query<(Entity, &BodyInfo), With<Player>>,
+    mut field: ResMut<Field>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    let died: Vec<Entity> = events
+        .read()
+        .map(|event| event.snake)
+        .filter(|snake| players.contains(*snake))
+        .collect();
+    if died.is_empty() {
+        return;
+    }
+    if players.iter().all(|(snake, _)| died.contains(&snake)) {
+        next_state.set(GameState::GameOver);
+        return;
+    }
+    // vacated here, the fixed update systems could otherwise still collide with the dead snake
+    for (snake, body_info) in players.iter_many(&died) {
+        for fragment in body_info.body.iter() {
+            field.vacate(*fragment);
+            commands.entity(*fragment).despawn_recursive();
+        }
+        field.vacate(snake);
+        commands.entity(snake).despawn_recursive();
+    }
+}
+
+// stepped snakes change their Cell on ticks
 pub fn check_if_on_new_cell(
-    mut player_query: Query<(&mut Cell, &Transform), With<Player>>,
+    mut player_query: Query<(Entity, &mut Cell, &Transform), (With<Player>, Without<GridStep>)>,
     mut events: EventWriter<MovedOntoNewCellEvent>,
     field: Res<Field>,
 ) {
-    for (mut cell, transform) in player_query.iter_mut() {
+    for (snake, mut cell, transform) in player_query.iter_mut() {
         let current_cell = field.cell(transform.translation.xz());
         if current_cell != *cell {
             *cell = current_cell;
-            events.send(MovedOntoNewCellEvent {});
+            events.send(MovedOntoNewCellEvent {
+                snake,
+                cell: current_cell,
+            });
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::enter_game_over;
+    use crate::field::{Cell, Field, OccupantKind};
+    use crate::player::components::{BodyInfo, Player};
+    use crate::snake_mesh::SnakeDied;
+    use crate::states::GameState;
+    use bevy::prelude::*;
+
+    fn spawn_snake(app: &mut App, cell: Cell) -> Entity {
+        let fragment = app.world.spawn_empty().id();
+        let snake = app
+            .world
+            .spawn((
+                Player,
+                BodyInfo {
+                    body: vec![fragment],
+                    first_gap: 1.0,
+                    gap: 1.0,
+                    tail_extension: 1.0,
+                },
+            ))
+            .id();
+        let mut field = app.world.resource_mut::<Field>();
+        field.occupy(snake, OccupantKind::SnakeHead, &cell);
+        field.occupy(fragment, OccupantKind::SnakeBody, &cell);
+        snake
+    }
+
+    #[test]
+    fn test_other_snakes_keep_playing() {
+        let mut app = App::new();
+        app.init_state::<GameState>()
+            .insert_resource(Field::new(
+                IVec2::new(10, 10),
+                Vec2::splat(10.0),
+                Vec2::ZERO,
+            ))
+            .add_event::<SnakeDied>()
+            .add_systems(Update, enter_game_over);
+        let dead = spawn_snake(&mut app, Cell::new(1, 1));
+        let alive = spawn_snake(&mut app, Cell::new(5, 5));
+
+        app.world.send_event(SnakeDied { snake: dead });
+        app.update();
+
+        assert!(app.world.get_entity(dead).is_none());
+        assert!(app.world.get_entity(alive).is_some());
+        assert_eq!(app.world.resource::<NextState<GameState>>().0, None);
+        let field = app.world.resource::<Field>();
+        assert!(field.occupants(&Cell::new(1, 1)).is_empty());
+        assert_eq!(field.occupants(&Cell::new(5, 5)).len(), 2);
+
+        app.world.send_event(SnakeDied { snake: alive });
+        app.update();
+
+        assert!(app.world.get_entity(alive).is_some());
+        assert_eq!(
+            app.world.resource::<NextState<GameState>>().0,
+            Some(GameState::GameOver)
+        );
+    }
+}
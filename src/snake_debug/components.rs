@@ -0,0 +1,8 @@
+use bevy::prelude::*;
+
+// lives on the snake, points to the plane showing the slice of its sdf
+#[derive(Component)]
+pub struct SdfSlice {
+    pub plane: Entity,
+    pub image: Handle<Image>,
+}
@@ -0,0 +1,26 @@
+mod components;
+mod resources;
+mod systems;
+
+use bevy::prelude::*;
+
+pub use resources::SnakeDebugSettings;
+
+pub struct SnakeDebugPlugin;
+impl Plugin for SnakeDebugPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SnakeDebugSettings>()
+            .register_type::<SnakeDebugSettings>()
+            .add_systems(
+                Update,
+                (
+                    systems::toggle_debug,
+                    systems::feed_snakes,
+                    systems::kill_snakes,
+                    systems::draw_polygonization_grids,
+                    systems::update_sdf_slices,
+                )
+                    .chain(),
+            );
+    }
+}
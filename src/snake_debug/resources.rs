@@ -0,0 +1,47 @@
+use bevy::prelude::*;
+
+use crate::snake_mesh::DeathStyle;
+
+#[derive(Resource, Reflect, Clone, Debug)]
+#[reflect(Resource)]
+pub struct SnakeDebugSettings {
+    pub enabled: bool,
+    pub toggle_key: KeyCode,
+    // sends PreySwallowed to every snake
+    pub prey_key: KeyCode,
+    // starts the death animation on every snake
+    pub death_key: KeyCode,
+    pub death_style: DeathStyle,
+    pub death_duration: f32,
+    pub draw_bounds: bool,
+    pub bounds_color: Color,
+    pub draw_lattice: bool,
+    pub lattice_color: Color,
+    pub draw_slice: bool,
+    // in the snake's local space
+    pub slice_height: f32,
+    pub slice_resolution: u32,
+    // distance at which the slice color saturates
+    pub slice_range: f32,
+}
+
+impl Default for SnakeDebugSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            toggle_key: KeyCode::F3,
+            prey_key: KeyCode::F4,
+            death_key: KeyCode::F5,
+            death_style: DeathStyle::Deflate,
+            death_duration: 2.0,
+            draw_bounds: true,
+            bounds_color: Color::YELLOW,
+            draw_lattice: false,
+            lattice_color: Color::rgba(1.0, 1.0, 1.0, 0.15),
+            draw_slice: true,
+            slice_height: 0.0,
+            slice_resolution: 128,
+            slice_range: 5.0,
+        }
+    }
+}
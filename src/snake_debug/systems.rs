@@ -0,0 +1,219 @@
+use bevy::{
+    prelude::*,
+    render::{
+        render_asset::RenderAssetUsages,
+        render_resource::{Extent3d, TextureDimension, TextureFormat},
+    },
+};
+
+use super::components::SdfSlice;
+use super::resources::SnakeDebugSettings;
+
+use crate::snake_mesh::{PolygonizationSettings, PreySwallowed, Sdf, SnakeDeath, SnakeMesh};
+
+pub fn toggle_debug(key: Res<ButtonInput<KeyCode>>, mut settings: ResMut<SnakeDebugSettings>) {
+    if key.just_pressed(settings.toggle_key) {
+        settings.enabled = !settings.enabled;
+    }
+}
+
+pub fn feed_snakes(
+    key: Res<ButtonInput<KeyCode>>,
+    settings: Res<SnakeDebugSettings>,
+    snakes: Query<Entity, With<SnakeMesh>>,
+    mut events: EventWriter<PreySwallowed>,
+) {
+    if !settings.enabled || !key.just_pressed(settings.prey_key) {
+        return;
+    }
+    for snake in snakes.iter() {
+        events.send(PreySwallowed { snake, size: 1.0 });
+    }
+}
+
+pub fn kill_snakes(
+    mut commands: Commands,
+    key: Res<ButtonInput<KeyCode>>,
+    settings: Res<SnakeDebugSettings>,
+    snakes: Query<Entity, With<SnakeMesh>>,
+) {
+    if !settings.enabled || !key.just_pressed(settings.death_key) {
+        return;
+    }
+    for snake in snakes.iter() {
+        commands.entity(snake).insert(SnakeDeath::new(
+            settings.death_style,
+            settings.death_duration,
+        ));
+    }
+}
+
+pub fn draw_polygonization_grids(
+    mut gizmos: Gizmos,
+    settings: Res<SnakeDebugSettings>,
+    snakes: Query<(&GlobalTransform, &PolygonizationSettings), With<SnakeMesh>>,
+) {
+    if !settings.enabled {
+        return;
+    }
+    for (transform, polygonization) in snakes.iter() {
+        let origin = polygonization.grid_origin;
+        let size = polygonization.grid_size;
+        if settings.draw_bounds {
+            let bounds = transform.compute_transform()
+                * Transform::from_translation(origin + size / 2.0).with_scale(size);
+            gizmos.cuboid(bounds, settings.bounds_color);
+        }
+        if settings.draw_lattice {
+            let cell_size = polygonization.cell_size();
+            for i in 0..=polygonization.resolution {
+                for j in 0..=polygonization.resolution {
+                    let (i, j) = (i as f32, j as f32);
+                    let lines = [
+                        (
+                            Vec3::new(0.0, i * cell_size.y, j * cell_size.z),
+                            Vec3::X * size.x,
+                        ),
+                        (
+                            Vec3::new(i * cell_size.x, 0.0, j * cell_size.z),
+                            Vec3::Y * size.y,
+                        ),
+                        (
+                            Vec3::new(i * cell_size.x, j * cell_size.y, 0.0),
+                            Vec3::Z * size.z,
+                        ),
+                    ];
+                    for (start, direction) in lines {
+                        gizmos.line(
+                            transform.transform_point(origin + start),
+                            transform.transform_point(origin + start + direction),
+                            settings.lattice_color,
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
+
+pub fn update_sdf_slices(
+    mut commands: Commands,
+    settings: Res<SnakeDebugSettings>,
+    mut images: ResMut<Assets<Image>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    snakes: Query<(
+        Entity,
+        Ref<SnakeMesh>,
+        Ref<PolygonizationSettings>,
+        Option<&SdfSlice>,
+    )>,
+    mut planes: Query<&mut Transform>,
+) {
+    let show_slices = settings.enabled && settings.draw_slice;
+    for (entity, snake, polygonization, slice) in snakes.iter() {
+        if !show_slices {
+            if let Some(slice) = slice {
+                commands.entity(slice.plane).despawn_recursive();
+                commands.entity(entity).remove::<SdfSlice>();
+            }
+            continue;
+        }
+        // the image is expensive to build on the cpu
+        let changed = settings.is_changed() || snake.is_changed() || polygonization.is_changed();
+        if slice.is_some() && !changed {
+            continue;
+        }
+
+        let plane_transform = Transform::from_translation(Vec3::new(
+            polygonization.grid_origin.x + polygonization.grid_size.x / 2.0,
+            settings.slice_height,
+            polygonization.grid_origin.z + polygonization.grid_size.z / 2.0,
+        ))
+        .with_scale(Vec3::new(
+            polygonization.grid_size.x,
+            1.0,
+            polygonization.grid_size.z,
+        ));
+        let image = slice_image(snake, polygonization, &settings);
+
+        match slice {
+            Some(slice) => {
+                if let Some(existing) = images.get_mut(&slice.image) {
+                    *existing = image;
+                }
+                if let Ok(mut transform) = planes.get_mut(slice.plane) {
+                    *transform = plane_transform;
+                }
+            }
+            None => {
+                let image = images.add(image);
+                let plane = commands
+                    .spawn(PbrBundle {
+                        mesh: meshes.add(Plane3d::default().mesh().size(1.0, 1.0)),
+                        material: materials.add(StandardMaterial {
+                            base_color_texture: Some(image.clone()),
+                            alpha_mode: AlphaMode::Blend,
+                            unlit: true,
+                            cull_mode: None,
+                            ..default()
+                        }),
+                        transform: plane_transform,
+                        ..default()
+                    })
+                    .id();
+                commands
+                    .entity(entity)
+                    .add_child(plane)
+                    .insert(SdfSlice { plane, image });
+            }
+        }
+    }
+}
+
+fn slice_image(
+    snake: &SnakeMesh,
+    polygonization: &PolygonizationSettings,
+    settings: &SnakeDebugSettings,
+) -> Image {
+    let sdf = snake.sdf();
+    let resolution = settings.slice_resolution.max(1);
+    let mut data = Vec::with_capacity((resolution * resolution * 4) as usize);
+    for y in 0..resolution {
+        for x in 0..resolution {
+            let uv = (Vec2::new(x as f32, y as f32) + 0.5) / resolution as f32;
+            let point = Vec3::new(
+                polygonization.grid_origin.x + uv.x * polygonization.grid_size.x,
+                settings.slice_height,
+                polygonization.grid_origin.z + uv.y * polygonization.grid_size.z,
+            );
+            data.extend_from_slice(&slice_color(sdf.distance(point), settings.slice_range));
+        }
+    }
+    Image::new(
+        Extent3d {
+            width: resolution,
+            height: resolution,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::default(),
+    )
+}
+
+// blue inside, orange outside, white on the surface
+fn slice_color(distance: f32, range: f32) -> [u8; 4] {
+    let range = range.max(f32::EPSILON);
+    if distance.abs() < range * 0.02 {
+        return [255, 255, 255, 255];
+    }
+    let (near, far) = if distance < 0.0 {
+        (Vec3::new(0.2, 0.5, 1.0), Vec3::new(0.0, 0.0, 0.15))
+    } else {
+        (Vec3::new(1.0, 0.6, 0.1), Vec3::new(0.15, 0.0, 0.0))
+    };
+    let color = near.lerp(far, (distance.abs() / range).clamp(0.0, 1.0)) * 255.0;
+    [color.x as u8, color.y as u8, color.z as u8, 200]
+}
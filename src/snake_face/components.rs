@@ -0,0 +1,106 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use rand::Rng;
+
+// Procedural eyes and tongue on the head of a SnakeMesh, the head is the first body point.
+// Sizes are relative to SnakeMesh::radius
+#[derive(Component, Clone, Debug, PartialEq, Reflect)]
+#[reflect(Component)]
+pub struct SnakeFace {
+    // x to the side, y up, z forward
+    pub eye_offset: Vec3,
+    pub eye_radius: f32,
+    pub eye_smoothness: f32,
+    // average seconds between blinks
+    pub blink_interval: f32,
+    pub blink_duration: f32,
+    // average seconds between tongue flicks
+    pub tongue_interval: f32,
+    pub tongue_duration: f32,
+    pub tongue_length: f32,
+    pub tongue_color: Color,
+}
+
+impl Default for SnakeFace {
+    fn default() -> Self {
+        Self {
+            eye_offset: Vec3::new(0.55, 0.45, 0.35),
+            eye_radius: 0.28,
+            eye_smoothness: 0.08,
+            blink_interval: 4.0,
+            blink_duration: 0.2,
+            tongue_interval: 2.5,
+            tongue_duration: 0.4,
+            tongue_length: 1.5,
+            tongue_color: Color::rgb(0.8, 0.1, 0.2),
+        }
+    }
+}
+
+// state of the SnakeFace animations
+#[derive(Component)]
+pub struct FaceAnimation {
+    pub blink: RecurringAnimation,
+    pub tongue_flick: RecurringAnimation,
+    pub tongue: Entity,
+}
+
+// Plays for `duration` seconds after random pauses around `interval` seconds
+pub struct RecurringAnimation {
+    pause: Timer,
+    animation: Option<Timer>,
+}
+
+impl RecurringAnimation {
+    pub fn new(interval: f32) -> Self {
+        Self {
+            pause: random_pause(interval),
+            animation: None,
+        }
+    }
+
+    // fraction of the animation which has played, None during the pause
+    pub fn tick(&mut self, delta: Duration, interval: f32, duration: f32) -> Option<f32> {
+        if let Some(animation) = self.animation.as_mut() {
+            animation.tick(delta);
+            if !animation.finished() {
+                return Some(animation.fraction());
+            }
+            self.animation = None;
+            self.pause = random_pause(interval);
+            return None;
+        }
+        self.pause.tick(delta);
+        if self.pause.finished() {
+            self.animation = Some(Timer::from_seconds(duration, TimerMode::Once));
+            return Some(0.0);
+        }
+        None
+    }
+}
+
+fn random_pause(interval: f32) -> Timer {
+    let seconds = interval * rand::thread_rng().gen_range(0.5..1.5);
+    Timer::from_seconds(seconds, TimerMode::Once)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RecurringAnimation;
+    use std::time::Duration;
+
+    #[test]
+    fn test_recurring_animation() {
+        let mut animation = RecurringAnimation::new(1.0);
+        assert_eq!(animation.tick(Duration::from_millis(400), 1.0, 1.0), None);
+        assert_eq!(
+            animation.tick(Duration::from_millis(1100), 1.0, 1.0),
+            Some(0.0)
+        );
+        let progress = animation.tick(Duration::from_millis(500), 1.0, 1.0);
+        assert!((progress.unwrap() - 0.5).abs() < 1e-6);
+        assert_eq!(animation.tick(Duration::from_millis(500), 1.0, 1.0), None);
+        assert_eq!(animation.tick(Duration::from_millis(400), 1.0, 1.0), None);
+    }
+}
@@ -0,0 +1,21 @@
+mod components;
+mod systems;
+
+use bevy::prelude::*;
+
+pub use components::SnakeFace;
+
+pub struct SnakeFacePlugin;
+impl Plugin for SnakeFacePlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<SnakeFace>().add_systems(
+            Update,
+            (
+                systems::remove_faces,
+                systems::setup_faces,
+                systems::animate_faces,
+            )
+                .chain(),
+        );
+    }
+}
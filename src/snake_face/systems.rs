@@ -0,0 +1,200 @@
+use std::f32::consts::PI;
+
+use bevy::prelude::*;
+
+use super::components::{FaceAnimation, RecurringAnimation, SnakeFace};
+
+use crate::snake_mesh::{SnakeDead, SnakeDeath, SnakeEyes, SnakeMesh};
+
+pub fn setup_faces(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    faces: Query<(Entity, &SnakeFace), Without<FaceAnimation>>,
+) {
+    for (entity, face) in faces.iter() {
+        let material = materials.add(StandardMaterial {
+            base_color: face.tongue_color,
+            ..default()
+        });
+        // one unit long along +Z, scaled by animate_faces
+        let stem = meshes.add(Cuboid::new(0.06, 0.02, 0.8));
+        let fork = meshes.add(Cuboid::new(0.04, 0.02, 0.3));
+        let parts = [
+            (stem, Transform::from_xyz(0.0, 0.0, 0.4)),
+            (
+                fork.clone(),
+                Transform::from_xyz(0.05, 0.0, 0.9).with_rotation(Quat::from_rotation_y(0.35)),
+            ),
+            (
+                fork,
+                Transform::from_xyz(-0.05, 0.0, 0.9).with_rotation(Quat::from_rotation_y(-0.35)),
+            ),
+        ];
+        let tongue = commands
+            .spawn(SpatialBundle {
+                visibility: Visibility::Hidden,
+                ..default()
+            })
+            .with_children(|tongue| {
+                for (mesh, transform) in parts {
+                    tongue.spawn(PbrBundle {
+                        mesh,
+                        material: material.clone(),
+                        transform,
+                        ..default()
+                    });
+                }
+            })
+            .id();
+        commands
+            .entity(entity)
+            .add_child(tongue)
+            .insert(FaceAnimation {
+                blink: RecurringAnimation::new(face.blink_interval),
+                tongue_flick: RecurringAnimation::new(face.tongue_interval),
+                tongue,
+            });
+    }
+}
+
+pub fn remove_faces(
+    mut commands: Commands,
+    mut removed: RemovedComponents<SnakeFace>,
+    mut snakes: Query<(&FaceAnimation, &mut SnakeMesh)>,
+) {
+    for entity in removed.read() {
+        let Ok((animation, mut snake)) = snakes.get_mut(entity) else {
+            continue;
+        };
+        snake.eyes = None;
+        commands.entity(animation.tongue).despawn_recursive();
+        commands.entity(entity).remove::<FaceAnimation>();
+    }
+}
+
+pub fn animate_faces(
+    time: Res<Time>,
+    mut snakes: Query<(
+        &SnakeFace,
+        &mut FaceAnimation,
+        &mut SnakeMesh,
+        Has<SnakeDeath>,
+        Has<SnakeDead>,
+    )>,
+    mut tongues: Query<(&mut Transform, &mut Visibility)>,
+) {
+    for (face, mut animation, mut snake, dying, dead) in snakes.iter_mut() {
+        let Some(head) = snake.body.first().copied() else {
+            continue;
+        };
+        // the snake lies in the local xz plane
+        let forward = snake
+            .body
+            .get(1)
+            .map(|neck| (head - *neck).normalize_or_zero())
+            .filter(|forward| *forward != Vec3::ZERO)
+            .unwrap_or(Vec3::Z);
+        let side = Vec3::Y.cross(forward).try_normalize().unwrap_or(Vec3::X);
+        let up = forward.cross(side);
+
+        let blink = animation
+            .blink
+            .tick(time.delta(), face.blink_interval, face.blink_duration);
+        let offset = face.eye_offset * snake.radius;
+        let eye = |side_sign: f32| {
+            head + side * offset.x * side_sign + up * offset.y + forward * offset.z
+        };
+        let eyes = Some(SnakeEyes {
+            left: eye(1.0),
+            right: eye(-1.0),
+            // shrink with the head when the snake deflates
+            radius: face.eye_radius * snake.radius * snake.profile.deflation_scale(0.0),
+            openness: blink.map_or(1.0, |progress| 1.0 - (progress * PI).sin()),
+            smoothness: face.eye_smoothness * snake.radius,
+        });
+        // keeps change detection quiet while nothing moves
+        if snake.eyes != eyes {
+            snake.eyes = eyes;
+        }
+
+        let flick =
+            animation
+                .tongue_flick
+                .tick(time.delta(), face.tongue_interval, face.tongue_duration);
+        let Ok((mut transform, mut visibility)) = tongues.get_mut(animation.tongue) else {
+            continue;
+        };
+        let Some(progress) = flick.filter(|_| !dying && !dead) else {
+            visibility.set_if_neq(Visibility::Hidden);
+            continue;
+        };
+        let head_radius = snake.radius * snake.profile.sample(0.0);
+        let length = face.tongue_length * snake.radius;
+        let extension = (progress * PI).sin().max(1e-3);
+        visibility.set_if_neq(Visibility::Inherited);
+        *transform = Transform::from_translation(head + forward * head_radius * 0.9)
+            .with_rotation(Quat::from_rotation_arc(Vec3::Z, forward))
+            .with_scale(Vec3::new(length, length, length * extension));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bevy::prelude::*;
+
+    use super::{animate_faces, setup_faces};
+    use crate::snake_face::components::FaceAnimation;
+    use crate::snake_face::SnakeFace;
+    use crate::snake_mesh::{RadiusProfile, SnakeColors, SnakeDead, SnakeMesh};
+
+    // visibility of the tongue after every update, with tongue flicks as often as possible
+    fn tongue_visibilities(dead: bool) -> Vec<Visibility> {
+        let mut app = App::new();
+        app.init_resource::<Time>()
+            .init_resource::<Assets<Mesh>>()
+            .init_resource::<Assets<StandardMaterial>>()
+            .add_systems(Update, (setup_faces, animate_faces).chain());
+        let mut snake = app.world.spawn((
+            SnakeFace {
+                tongue_interval: 0.1,
+                tongue_duration: 100.0,
+                ..default()
+            },
+            SnakeMesh {
+                radius: 1.0,
+                body: vec![Vec3::ZERO, Vec3::NEG_Z],
+                profile: RadiusProfile::snake(),
+                colors: SnakeColors::default(),
+                eyes: None,
+                dissolve: None,
+                fake_mesh_asset: AssetId::default(),
+            },
+        ));
+        if dead {
+            snake.insert(SnakeDead);
+        }
+        let snake = snake.id();
+
+        (0..5)
+            .map(|_| {
+                app.world
+                    .resource_mut::<Time>()
+                    .advance_by(Duration::from_secs(1));
+                app.update();
+                let tongue = app.world.get::<FaceAnimation>(snake).unwrap().tongue;
+                *app.world.get::<Visibility>(tongue).unwrap()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_tongue_hidden_after_death() {
+        assert!(tongue_visibilities(false).contains(&Visibility::Inherited));
+        assert!(tongue_visibilities(true)
+            .iter()
+            .all(|visibility| *visibility == Visibility::Hidden));
+    }
+}
@@ -0,0 +1,73 @@
+use bevy::prelude::*;
+
+// Replaces the gradient at a single body point, like a segment flashing on damage
+#[derive(Copy, Clone, Debug, PartialEq, Reflect)]
+pub struct SegmentTint {
+    // index into SnakeMesh::body
+    pub segment: usize,
+    // alpha is how much of the gradient gets replaced
+    pub color: Color,
+}
+
+// Vertex colors along the snake body, multiplied with the material's base color
+#[derive(Clone, Debug, PartialEq, Reflect)]
+pub struct SnakeColors {
+    pub head: Color,
+    pub tail: Color,
+    pub tints: Vec<SegmentTint>,
+}
+
+impl Default for SnakeColors {
+    fn default() -> Self {
+        Self::solid(Color::WHITE)
+    }
+}
+
+impl SnakeColors {
+    pub fn solid(color: Color) -> Self {
+        Self::gradient(color, color)
+    }
+
+    pub fn gradient(head: Color, tail: Color) -> Self {
+        Self {
+            head,
+            tail,
+            tints: Vec::new(),
+        }
+    }
+
+    // linear rgba of a body point, position is 0.0 at the head and 1.0 at the tip of the tail
+    pub fn sample(&self, segment: usize, position: f32) -> Vec4 {
+        let head = Vec4::from(self.head.as_linear_rgba_f32());
+        let tail = Vec4::from(self.tail.as_linear_rgba_f32());
+        let gradient = head.lerp(tail, position.clamp(0.0, 1.0));
+        self.tints
+            .iter()
+            .filter(|tint| tint.segment == segment)
+            .fold(gradient, |color, tint| {
+                let tint = Vec4::from(tint.color.as_linear_rgba_f32());
+                color.lerp(tint.truncate().extend(color.w), tint.w)
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{SegmentTint, SnakeColors};
+    use bevy::prelude::*;
+
+    #[test]
+    fn test_sample() {
+        let mut colors = SnakeColors::gradient(Color::WHITE, Color::BLACK);
+        assert_eq!(colors.sample(0, 0.0), Vec4::ONE);
+        assert_eq!(colors.sample(3, 1.0), Vec4::new(0.0, 0.0, 0.0, 1.0));
+        assert_eq!(colors.sample(1, 0.5), Vec4::new(0.5, 0.5, 0.5, 1.0));
+
+        colors.tints.push(SegmentTint {
+            segment: 1,
+            color: Color::rgba(1.0, 0.0, 0.0, 0.5),
+        });
+        assert_eq!(colors.sample(1, 0.0), Vec4::new(1.0, 0.5, 0.5, 1.0));
+        assert_eq!(colors.sample(2, 0.0), Vec4::ONE);
+    }
+}
@@ -1,14 +1,153 @@
 use bevy::prelude::*;
 
+use super::{
+    colors::SnakeColors,
+    profile::RadiusProfile,
+    sdf::{SnakeDissolve, SnakeEyes, SnakeSdf},
+};
+
+// cells along every axis of the polygonization grid, multiples of the compute workgroup size
+pub const MIN_GRID_RESOLUTION: u32 = 32;
+pub const MAX_GRID_RESOLUTION: u32 = 64;
+// enclosing grids grow with the body, so long snakes keep the detail of short ones
+const CELLS_PER_RADIUS: f32 = 4.0;
+
 #[derive(Component, Copy, Clone, Debug, PartialEq, Reflect)]
 pub struct PolygonizationSettings {
     pub grid_size: Vec3,
     pub grid_origin: Vec3,
+    // cells along every axis, between MIN_GRID_RESOLUTION and MAX_GRID_RESOLUTION
+    pub resolution: u32,
+    // smoothing passes over the vertices, hides the stairs of low resolution grids. 0 disables it
+    pub relaxation_iterations: u32,
 }
 
-#[derive(Component, Copy, Clone, Debug, PartialEq, Reflect)]
+impl PolygonizationSettings {
+    pub fn cell_size(&self) -> Vec3 {
+        self.grid_size / self.resolution as f32
+    }
+
+    // smallest grid around the whole body, the margin keeps the surface away from the border
+    pub fn enclosing(snake: &SnakeMesh) -> Self {
+        let margin = Vec3::splat(snake.radius * snake.profile.max_scale() * 1.5);
+        let (min, max) = snake
+            .body
+            .iter()
+            .fold((Vec3::MAX, Vec3::MIN), |(min, max), point| {
+                (min.min(*point), max.max(*point))
+            });
+        if snake.body.is_empty() {
+            return Self {
+                grid_size: margin * 2.0,
+                grid_origin: -margin,
+                resolution: MIN_GRID_RESOLUTION,
+                relaxation_iterations: 0,
+            };
+        }
+        let grid_size = max - min + margin * 2.0;
+        let cells = grid_size.max_element() / snake.radius * CELLS_PER_RADIUS;
+        let resolution = (cells.ceil() as u32)
+            .clamp(MIN_GRID_RESOLUTION, MAX_GRID_RESOLUTION)
+            .next_multiple_of(8);
+        Self {
+            grid_size,
+            grid_origin: min - margin,
+            resolution,
+            relaxation_iterations: 0,
+        }
+    }
+}
+
+#[derive(Component, Clone, Debug, PartialEq, Reflect)]
 pub struct SnakeMesh {
     pub radius: f32,
-    pub center: Vec3,
+    // from head to tail, in local space
+    pub body: Vec<Vec3>,
+    pub profile: RadiusProfile,
+    pub colors: SnakeColors,
+    // driven by SnakeFace when there is one
+    pub eyes: Option<SnakeEyes>,
+    // driven by SnakeDeath with DeathStyle::Dissolve
+    pub dissolve: Option<SnakeDissolve>,
     pub fake_mesh_asset: AssetId<Mesh>,
 }
+
+impl SnakeMesh {
+    // the same shape the compute shader polygonizes
+    pub fn sdf(&self) -> SnakeSdf {
+        SnakeSdf::with_profile(self.radius, self.body.clone(), &self.profile)
+            .with_colors(&self.colors)
+            .with_eyes(self.eyes)
+            .with_dissolve(self.dissolve)
+    }
+}
+
+// How bulges started by PreySwallowed look on this snake
+#[derive(Component, Copy, Clone, Debug, PartialEq, Reflect)]
+#[reflect(Component)]
+pub struct PreyBulgeSettings {
+    // seconds to travel from the head to the tail
+    pub duration: f32,
+    // in body lengths
+    pub width: f32,
+    pub strength: f32,
+}
+
+impl Default for PreyBulgeSettings {
+    fn default() -> Self {
+        Self {
+            duration: 3.0,
+            width: 0.08,
+            strength: 0.6,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Reflect)]
+pub enum DeathStyle {
+    // the radius shrinks to nothing from the tail to the head
+    Deflate,
+    // holes eat through the whole body, frequency is in noise cells per unit of length
+    Dissolve { frequency: f32 },
+}
+
+// Plays the death animation, replaced by SnakeDead once SnakeDied is sent
+#[derive(Component, Copy, Clone, Debug, PartialEq, Reflect)]
+#[reflect(Component)]
+pub struct SnakeDeath {
+    pub style: DeathStyle,
+    // in seconds
+    pub duration: f32,
+    pub elapsed: f32,
+}
+
+impl SnakeDeath {
+    pub fn new(style: DeathStyle, duration: f32) -> Self {
+        Self {
+            style,
+            duration,
+            elapsed: 0.0,
+        }
+    }
+
+    pub fn progress(&self) -> f32 {
+        (self.elapsed / self.duration.max(f32::EPSILON)).min(1.0)
+    }
+}
+
+// The death animation is over and the snake stays invisible
+#[derive(Component, Copy, Clone, Debug, PartialEq, Reflect)]
+#[reflect(Component)]
+pub struct SnakeDead;
+
+// Draws a debug visualization of the polygonizer output on top of the snake's own material
+#[derive(Component, Copy, Clone, Debug, Default, PartialEq, Eq, Reflect)]
+#[reflect(Component)]
+pub enum SnakeDebugView {
+    #[default]
+    Disabled,
+    // world space normals as colors
+    Normals,
+    // position of every vertex inside of its grid cell as colors
+    CellPlacement,
+}
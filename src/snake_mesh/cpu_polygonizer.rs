@@ -0,0 +1,362 @@
+use bevy::{
+    prelude::*,
+    render::{mesh::Indices, render_asset::RenderAssetUsages, render_resource::PrimitiveTopology},
+    tasks::{block_on, futures_lite::future, AsyncComputeTaskPool, Task},
+};
+
+use super::{
+    components::{PolygonizationSettings, SnakeMesh},
+    sdf::{Sdf, SnakeSdf},
+};
+
+// same order as in snake_compute.wgsl
+const EDGES: [(usize, usize); 12] = [
+    (0, 1),
+    (0, 2),
+    (0, 4),
+    (1, 3),
+    (1, 5),
+    (2, 3),
+    (2, 6),
+    (3, 7),
+    (4, 5),
+    (4, 6),
+    (5, 7),
+    (6, 7),
+];
+
+// face neighbors averaged by the relaxation, same as in relax_vertices
+const NEIGHBORS: [IVec3; 6] = [
+    IVec3::X,
+    IVec3::NEG_X,
+    IVec3::Y,
+    IVec3::NEG_Y,
+    IVec3::Z,
+    IVec3::NEG_Z,
+];
+// matches RELAXATION_FACTOR in snake_compute.wgsl
+const RELAXATION_FACTOR: f32 = 0.5;
+
+#[derive(Copy, Clone, Default)]
+struct CellInfo {
+    vbo_index: u32,
+    intersections_bitmask: u32,
+}
+
+// Used instead of the compute shader when the adapter can't run it
+#[derive(Component, Default)]
+pub struct CpuPolygonization {
+    task: Option<Task<Mesh>>,
+    dirty: bool,
+}
+
+// cpu version of find_vertices and connect_vertices from snake_compute.wgsl
+pub fn polygonize(sdf: &impl Sdf, settings: &PolygonizationSettings) -> Mesh {
+    let resolution = settings.resolution as usize;
+    let cell_size = settings.cell_size();
+    let flat_index =
+        |x: usize, y: usize, z: usize| x + y * resolution + z * resolution * resolution;
+
+    let mut positions: Vec<Vec3> = Vec::new();
+    let mut cells = vec![CellInfo::default(); resolution * resolution * resolution];
+    for z in 0..resolution {
+        for y in 0..resolution {
+            for x in 0..resolution {
+                let cell_origin =
+                    settings.grid_origin + Vec3::new(x as f32, y as f32, z as f32) * cell_size;
+                let corners: [Vec3; 8] = std::array::from_fn(|i| {
+                    let offset = Vec3::new((i & 1) as f32, ((i >> 1) & 1) as f32, (i >> 2) as f32);
+                    cell_origin + offset * cell_size
+                });
+                let distances = corners.map(|corner| sdf.distance(corner));
+
+                let mut sum = Vec3::ZERO;
+                let mut intersections_count = 0;
+                let mut intersections_bitmask = 0;
+                for (i, (p0, p1)) in EDGES.iter().enumerate() {
+                    let (sdf0, sdf1) = (distances[*p0], distances[*p1]);
+                    if (sdf0 > 0.0) != (sdf1 > 0.0) {
+                        intersections_bitmask |= 1 << i;
+                        let ratio = sdf0 / (sdf0 - sdf1);
+                        sum += (1.0 - ratio) * corners[*p0] + ratio * corners[*p1];
+                        intersections_count += 1;
+                    }
+                }
+                let mut vbo_index = 0;
+                if intersections_count > 0 {
+                    let point = sum / intersections_count as f32;
+                    vbo_index = positions.len() as u32;
+                    positions.push(point);
+                }
+                cells[flat_index(x, y, z)] = CellInfo {
+                    vbo_index,
+                    intersections_bitmask,
+                };
+            }
+        }
+    }
+
+    for _ in 0..settings.relaxation_iterations {
+        positions = relax_vertices(sdf, settings.resolution, &cells, &positions);
+    }
+
+    let mut indices: Vec<u32> = Vec::new();
+    let mut write_quad = |p0: u32, p1: u32, p2: u32, p3: u32| {
+        indices.extend_from_slice(&[p0, p1, p2, p1, p3, p2]);
+    };
+    for z in 0..resolution {
+        for y in 0..resolution {
+            for x in 0..resolution {
+                let cell = cells[flat_index(x, y, z)];
+                let vbo_index = |x: usize, y: usize, z: usize| cells[flat_index(x, y, z)].vbo_index;
+                if cell.intersections_bitmask & 1 != 0 && y != 0 && z != 0 {
+                    write_quad(
+                        cell.vbo_index,
+                        vbo_index(x, y - 1, z),
+                        vbo_index(x, y, z - 1),
+                        vbo_index(x, y - 1, z - 1),
+                    );
+                }
+                if cell.intersections_bitmask & 2 != 0 && x != 0 && z != 0 {
+                    write_quad(
+                        cell.vbo_index,
+                        vbo_index(x - 1, y, z),
+                        vbo_index(x, y, z - 1),
+                        vbo_index(x - 1, y, z - 1),
+                    );
+                }
+                if cell.intersections_bitmask & 4 != 0 && x != 0 && y != 0 {
+                    write_quad(
+                        cell.vbo_index,
+                        vbo_index(x - 1, y, z),
+                        vbo_index(x, y - 1, z),
+                        vbo_index(x - 1, y - 1, z),
+                    );
+                }
+            }
+        }
+    }
+
+    let mut mesh = Mesh::new(
+        PrimitiveTopology::TriangleList,
+        RenderAssetUsages::default(),
+    );
+    let normals: Vec<[f32; 3]> = positions
+        .iter()
+        .map(|point| sdf.normal(*point).to_array())
+        .collect();
+    let colors: Vec<[f32; 4]> = positions
+        .iter()
+        .map(|point| sdf.color(*point).to_array())
+        .collect();
+    let positions: Vec<[f32; 3]> = positions.iter().map(|point| point.to_array()).collect();
+    mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, normals);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_COLOR, colors);
+    mesh.insert_indices(Indices::U32(indices));
+    mesh
+}
+
+// cpu version of relax_vertices and apply_relaxed_vertices from snake_compute.wgsl
+fn relax_vertices(
+    sdf: &impl Sdf,
+    resolution: u32,
+    cells: &[CellInfo],
+    positions: &[Vec3],
+) -> Vec<Vec3> {
+    let resolution = resolution as i32;
+    let flat_index =
+        |cell: IVec3| (cell.x + cell.y * resolution + cell.z * resolution * resolution) as usize;
+    let mut relaxed = positions.to_vec();
+    for z in 0..resolution {
+        for y in 0..resolution {
+            for x in 0..resolution {
+                let cell_position = IVec3::new(x, y, z);
+                let cell = cells[flat_index(cell_position)];
+                if cell.intersections_bitmask == 0 {
+                    continue;
+                }
+                let position = positions[cell.vbo_index as usize];
+                let mut sum = Vec3::ZERO;
+                let mut neighbors_count = 0;
+                for offset in NEIGHBORS {
+                    let neighbor = cell_position + offset;
+                    if neighbor.cmplt(IVec3::ZERO).any()
+                        || neighbor.cmpge(IVec3::splat(resolution)).any()
+                    {
+                        continue;
+                    }
+                    let neighbor_cell = cells[flat_index(neighbor)];
+                    if neighbor_cell.intersections_bitmask != 0 {
+                        sum += positions[neighbor_cell.vbo_index as usize];
+                        neighbors_count += 1;
+                    }
+                }
+                let target = if neighbors_count > 0 {
+                    position.lerp(sum / neighbors_count as f32, RELAXATION_FACTOR)
+                } else {
+                    position
+                };
+                relaxed[cell.vbo_index as usize] = sdf.closest_point(target);
+            }
+        }
+    }
+    relaxed
+}
+
+pub fn queue_cpu_polygonization(
+    mut commands: Commands,
+    mut snakes: Query<
+        (Entity, Option<&mut CpuPolygonization>),
+        (
+            With<SnakeMesh>,
+            With<PolygonizationSettings>,
+            Or<(Changed<SnakeMesh>, Changed<PolygonizationSettings>)>,
+        ),
+    >,
+) {
+    for (entity, polygonization) in snakes.iter_mut() {
+        match polygonization {
+            Some(mut polygonization) => polygonization.dirty = true,
+            None => {
+                commands.entity(entity).insert(CpuPolygonization {
+                    task: None,
+                    dirty: true,
+                });
+            }
+        }
+    }
+}
+
+pub fn apply_cpu_polygonization(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut snakes: Query<(
+        Entity,
+        &SnakeMesh,
+        &PolygonizationSettings,
+        &mut CpuPolygonization,
+        Option<&Handle<Mesh>>,
+    )>,
+) {
+    let task_pool = AsyncComputeTaskPool::get();
+    for (entity, snake, settings, mut polygonization, mesh_handle) in snakes.iter_mut() {
+        if let Some(task) = polygonization.task.as_mut() {
+            let Some(mesh) = block_on(future::poll_once(task)) else {
+                continue;
+            };
+            polygonization.task = None;
+            match mesh_handle {
+                Some(mesh_handle) => {
+                    meshes.insert(mesh_handle, mesh);
+                }
+                None => {
+                    commands.entity(entity).insert(meshes.add(mesh));
+                }
+            }
+        }
+
+        if polygonization.dirty {
+            polygonization.dirty = false;
+            let sdf: SnakeSdf = snake.sdf();
+            let settings = *settings;
+            polygonization.task = Some(task_pool.spawn(async move { polygonize(&sdf, &settings) }));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::polygonize;
+    use crate::snake_mesh::{PolygonizationSettings, Sdf, SnakeSdf, MIN_GRID_RESOLUTION};
+    use bevy::{prelude::*, render::mesh::VertexAttributeValues};
+
+    #[test]
+    fn test_vertices_are_on_surface() {
+        let settings = PolygonizationSettings {
+            grid_size: Vec3::splat(20.0),
+            grid_origin: Vec3::splat(-10.0),
+            resolution: MIN_GRID_RESOLUTION,
+            relaxation_iterations: 0,
+        };
+        let sphere = SnakeSdf::new(5.0, vec![Vec3::new(0.5, 0.0, -0.5)]);
+        let mesh = polygonize(&sphere, &settings);
+
+        let Some(VertexAttributeValues::Float32x3(positions)) =
+            mesh.attribute(Mesh::ATTRIBUTE_POSITION)
+        else {
+            panic!("mesh has no positions");
+        };
+        assert!(!positions.is_empty());
+        let cell_size = settings.cell_size().max_element();
+        for position in positions {
+            assert!(sphere.distance(Vec3::from_array(*position)).abs() < cell_size);
+        }
+        assert!(mesh.indices().is_some_and(|indices| indices.len() % 6 == 0));
+    }
+
+    #[test]
+    fn test_relaxed_vertices_are_on_surface() {
+        let mut settings = PolygonizationSettings {
+            grid_size: Vec3::splat(20.0),
+            grid_origin: Vec3::splat(-10.0),
+            resolution: MIN_GRID_RESOLUTION,
+            relaxation_iterations: 0,
+        };
+        let snake = SnakeSdf::new(
+            3.0,
+            vec![Vec3::new(-5.0, 0.0, 1.0), Vec3::new(5.0, 0.5, -1.0)],
+        );
+        let vertex_count = polygonize(&snake, &settings).count_vertices();
+        settings.relaxation_iterations = 3;
+        let mesh = polygonize(&snake, &settings);
+        assert_eq!(mesh.count_vertices(), vertex_count);
+
+        let Some(VertexAttributeValues::Float32x3(positions)) =
+            mesh.attribute(Mesh::ATTRIBUTE_POSITION)
+        else {
+            panic!("mesh has no positions");
+        };
+        for position in positions {
+            assert!(snake.distance(Vec3::from_array(*position)).abs() < 1e-3);
+        }
+    }
+
+    // mean of 1 - |cos| between the triangle normals and the sdf gradient, 0.0 without stairs
+    fn normal_deviation(mesh: &Mesh, sdf: &impl Sdf) -> f32 {
+        let Some(VertexAttributeValues::Float32x3(positions)) =
+            mesh.attribute(Mesh::ATTRIBUTE_POSITION)
+        else {
+            panic!("mesh has no positions");
+        };
+        let indices: Vec<usize> = mesh.indices().unwrap().iter().collect();
+        let deviations: Vec<f32> = indices
+            .chunks_exact(3)
+            .filter_map(|triangle| {
+                let [a, b, c] = [0, 1, 2].map(|i| Vec3::from_array(positions[triangle[i]]));
+                let face_normal = (b - a).cross(c - a).try_normalize()?;
+                let normal = sdf.gradient((a + b + c) / 3.0).try_normalize()?;
+                Some(1.0 - face_normal.dot(normal).abs())
+            })
+            .collect();
+        deviations.iter().sum::<f32>() / deviations.len() as f32
+    }
+
+    #[test]
+    fn test_relaxation_smooths_stairs() {
+        let mut settings = PolygonizationSettings {
+            grid_size: Vec3::splat(20.0),
+            grid_origin: Vec3::splat(-10.0),
+            resolution: MIN_GRID_RESOLUTION,
+            relaxation_iterations: 0,
+        };
+        let snake = SnakeSdf::new(
+            3.0,
+            vec![Vec3::new(-5.0, 0.0, 1.0), Vec3::new(5.0, 0.5, -1.0)],
+        );
+        let stairs = normal_deviation(&polygonize(&snake, &settings), &snake);
+        settings.relaxation_iterations = 3;
+        let relaxed = normal_deviation(&polygonize(&snake, &settings), &snake);
+        assert!(relaxed < stairs, "relaxed {relaxed}, not relaxed {stairs}");
+    }
+}
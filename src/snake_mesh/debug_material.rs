@@ -0,0 +1,55 @@
+use bevy::{
+    pbr::{MaterialPipeline, MaterialPipelineKey},
+    prelude::*,
+    render::{
+        mesh::MeshVertexBufferLayout,
+        render_resource::{
+            AsBindGroup, RenderPipelineDescriptor, ShaderRef, SpecializedMeshPipelineError,
+        },
+    },
+};
+
+use super::components::{PolygonizationSettings, SnakeDebugView};
+
+#[derive(AsBindGroup, Debug, Clone, Asset, TypePath)]
+pub struct SnakeDebugMaterial {
+    #[uniform(0)]
+    grid_origin: Vec3,
+    #[uniform(1)]
+    cell_size: Vec3,
+    #[uniform(2)]
+    mode: u32,
+}
+
+impl SnakeDebugMaterial {
+    pub fn new(settings: &PolygonizationSettings, view: SnakeDebugView) -> Self {
+        Self {
+            grid_origin: settings.grid_origin,
+            cell_size: settings.cell_size(),
+            mode: view as u32,
+        }
+    }
+}
+
+impl Material for SnakeDebugMaterial {
+    fn vertex_shader() -> ShaderRef {
+        "shaders/snake_debug.wgsl".into()
+    }
+
+    fn fragment_shader() -> ShaderRef {
+        "shaders/snake_debug.wgsl".into()
+    }
+
+    fn specialize(
+        _pipeline: &MaterialPipeline<Self>,
+        descriptor: &mut RenderPipelineDescriptor,
+        _layout: &MeshVertexBufferLayout,
+        _key: MaterialPipelineKey<Self>,
+    ) -> Result<(), SpecializedMeshPipelineError> {
+        // drawn on top of the snake's own material
+        if let Some(depth_stencil) = descriptor.depth_stencil.as_mut() {
+            depth_stencil.bias.slope_scale = 1.0;
+        }
+        Ok(())
+    }
+}
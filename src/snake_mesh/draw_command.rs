@@ -1,7 +1,7 @@
 use bevy::{
     ecs::system::{lifetimeless::SRes, SystemParamItem},
     log::*,
-    pbr::{MeshBindGroups, SetMaterialBindGroup, SetMeshViewBindGroup},
+    pbr::{MeshBindGroups, SetMaterialBindGroup, SetMeshViewBindGroup, SetPrepassViewBindGroup},
     render::{
         render_phase::{
             PhaseItem, RenderCommand, RenderCommandResult, SetItemPipeline, TrackedRenderPass,
@@ -20,6 +20,14 @@ pub type DrawSnake<M> = (
     DrawSnakeMesh,
 );
 
+pub type DrawSnakePrepass<M> = (
+    SetItemPipeline,
+    SetPrepassViewBindGroup<0>,
+    SetSnakeBindGroup<1>,
+    SetMaterialBindGroup<M, 2>,
+    DrawSnakeMesh,
+);
+
 pub struct DrawSnakeMesh;
 
 impl<P: PhaseItem> RenderCommand<P> for DrawSnakeMesh {
@@ -0,0 +1,15 @@
+use bevy::prelude::*;
+
+// Sent by gameplay when a snake eats, starts a bulge traveling from its head to its tail
+#[derive(Event, Clone, Copy, Debug)]
+pub struct PreySwallowed {
+    pub snake: Entity,
+    // multiplier of PreyBulgeSettings::strength
+    pub size: f32,
+}
+
+// Sent once the death animation of a snake has finished
+#[derive(Event, Clone, Copy, Debug)]
+pub struct SnakeDied {
+    pub snake: Entity,
+}
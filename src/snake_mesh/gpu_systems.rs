@@ -3,7 +3,9 @@ use std::hash::Hash;
 use bevy::{
     core_pipeline::{
         core_3d::{AlphaMask3d, Opaque3d, Transmissive3d, Transparent3d},
-        prepass::{DeferredPrepass, DepthPrepass, MotionVectorPrepass, NormalPrepass},
+        prepass::{
+            DeferredPrepass, DepthPrepass, MotionVectorPrepass, NormalPrepass, Opaque3dPrepass,
+        },
         tonemapping::{DebandDither, Tonemapping},
     },
     pbr::{
@@ -31,10 +33,12 @@ use bevy::{
 };
 
 use super::{
-    draw_command::DrawSnake,
-    pipelines::{SnakeComputePipeline, SnakeMaterialPipeline, SnakeMaterialPipelineKey},
+    draw_command::{DrawSnake, DrawSnakePrepass},
+    pipelines::{
+        SnakeComputePipeline, SnakeMaterialPipeline, SnakeMaterialPipelineKey, SnakePrepassPipeline,
+    },
     resources::{SnakeMeshInstance, SnakeMeshInstances, SnakeMeshUniforms},
-    PolygonizationSettings, SnakeMesh,
+    PolygonizationSettings, SnakeMesh, MAX_GRID_RESOLUTION,
 };
 
 #[allow(clippy::too_many_arguments)]
@@ -192,19 +196,6 @@ pub fn queue_material_snakes<M: Material>(
 
             mesh_key |= alpha_mode_pipeline_key(material.properties.alpha_mode);
 
-            let layout = MeshVertexBufferLayout::new(InnerMeshVertexBufferLayout::new(
-                [Mesh::ATTRIBUTE_POSITION.id].into(),
-                VertexBufferLayout {
-                    array_stride: Mesh::ATTRIBUTE_POSITION.format.size(),
-                    step_mode: VertexStepMode::Vertex,
-                    attributes: [VertexAttribute {
-                        shader_location: 0,
-                        offset: 0,
-                        format: Mesh::ATTRIBUTE_POSITION.format,
-                    }]
-                    .into(),
-                },
-            ));
             let pipeline_id = pipelines.specialize(
                 &pipeline_cache,
                 &material_pipeline,
@@ -214,7 +205,7 @@ pub fn queue_material_snakes<M: Material>(
                         bind_group_data: material.key.clone(),
                     },
                 },
-                &layout,
+                &snake_vertex_buffer_layout(),
             );
             let pipeline_id = match pipeline_id {
                 Ok(id) => id,
@@ -288,6 +279,142 @@ pub fn queue_material_snakes<M: Material>(
     }
 }
 
+#[allow(clippy::too_many_arguments)]
+pub fn queue_prepass_snakes<M: Material>(
+    opaque_draw_functions: Res<DrawFunctions<Opaque3dPrepass>>,
+    prepass_pipeline: Res<SnakePrepassPipeline<M>>,
+    mut pipelines: ResMut<SpecializedMeshPipelines<SnakePrepassPipeline<M>>>,
+    pipeline_cache: Res<PipelineCache>,
+    msaa: Res<Msaa>,
+    render_materials: Res<RenderMaterials<M>>,
+    snake_mesh_instances: Res<SnakeMeshInstances>,
+    render_material_instances: Res<RenderMaterialInstances<M>>,
+    mut views: Query<(
+        &VisibleEntities,
+        &mut RenderPhase<Opaque3dPrepass>,
+        (
+            Has<DepthPrepass>,
+            Has<NormalPrepass>,
+            Has<MotionVectorPrepass>,
+        ),
+    )>,
+) where
+    M::Data: PartialEq + Eq + Hash + Clone,
+{
+    let draw_prepass = opaque_draw_functions.read().id::<DrawSnakePrepass<M>>();
+    for (
+        visible_entities,
+        mut opaque_phase,
+        (depth_prepass, normal_prepass, motion_vector_prepass),
+    ) in &mut views
+    {
+        let mut view_key = MeshPipelineKey::from_msaa_samples(msaa.samples());
+        if depth_prepass {
+            view_key |= MeshPipelineKey::DEPTH_PREPASS;
+        }
+        if normal_prepass {
+            view_key |= MeshPipelineKey::NORMAL_PREPASS;
+        }
+        if motion_vector_prepass {
+            view_key |= MeshPipelineKey::MOTION_VECTOR_PREPASS;
+        }
+
+        for visible_entity in &visible_entities.entities {
+            let Some(material_asset_id) = render_material_instances.get(visible_entity) else {
+                continue;
+            };
+            let Some(snake_instance) = snake_mesh_instances.get(visible_entity) else {
+                continue;
+            };
+            let Some(material) = render_materials.get(material_asset_id) else {
+                continue;
+            };
+            // only opaque forward snakes are drawn in the prepass, everything else keeps
+            // relying on the main pass
+            if material.properties.alpha_mode != AlphaMode::Opaque
+                || material.properties.reads_view_transmission_texture
+                || material.properties.render_method != OpaqueRendererMethod::Forward
+            {
+                continue;
+            }
+
+            let mesh_key = view_key
+                | MeshPipelineKey::from_primitive_topology(PrimitiveTopology::TriangleList);
+            let pipeline_id = pipelines.specialize(
+                &pipeline_cache,
+                &prepass_pipeline,
+                SnakeMaterialPipelineKey {
+                    material_pipeline_key: MaterialPipelineKey {
+                        mesh_key,
+                        bind_group_data: material.key.clone(),
+                    },
+                },
+                &snake_vertex_buffer_layout(),
+            );
+            let pipeline_id = match pipeline_id {
+                Ok(id) => id,
+                Err(err) => {
+                    error!("{}", err);
+                    continue;
+                }
+            };
+
+            opaque_phase.add(Opaque3dPrepass {
+                entity: *visible_entity,
+                draw_function: draw_prepass,
+                pipeline_id,
+                asset_id: snake_instance.fake_mesh_asset,
+                batch_range: 0..1,
+                dynamic_offset: None,
+            });
+        }
+    }
+}
+
+// vbo is filled by the compute shader, one vertex is
+// [position: vec3<f32>, previous_position: vec3<f32>, normal: vec3<f32>, color: vec4<f32>]
+pub const SNAKE_VERTEX_STRIDE: u64 = 13 * 4;
+pub const SNAKE_PREVIOUS_POSITION_OFFSET: u64 = 3 * 4;
+pub const SNAKE_NORMAL_OFFSET: u64 = 6 * 4;
+pub const SNAKE_COLOR_OFFSET: u64 = 9 * 4;
+// prepass shader locations, picked to not collide with the ones of bevy's prepass
+pub const SNAKE_PREVIOUS_POSITION_SHADER_LOCATION: u32 = 10;
+pub const SNAKE_NORMAL_SHADER_LOCATION: u32 = 11;
+
+pub fn snake_vertex_buffer_layout() -> MeshVertexBufferLayout {
+    MeshVertexBufferLayout::new(InnerMeshVertexBufferLayout::new(
+        [
+            Mesh::ATTRIBUTE_POSITION.id,
+            Mesh::ATTRIBUTE_NORMAL.id,
+            Mesh::ATTRIBUTE_COLOR.id,
+        ]
+        .into(),
+        VertexBufferLayout {
+            array_stride: SNAKE_VERTEX_STRIDE,
+            step_mode: VertexStepMode::Vertex,
+            attributes: [
+                VertexAttribute {
+                    shader_location: 0,
+                    offset: 0,
+                    format: Mesh::ATTRIBUTE_POSITION.format,
+                },
+                VertexAttribute {
+                    shader_location: 1,
+                    offset: SNAKE_NORMAL_OFFSET,
+                    format: Mesh::ATTRIBUTE_NORMAL.format,
+                },
+                // picked up by the VERTEX_COLORS shader def of bevy's mesh pipeline
+                VertexAttribute {
+                    shader_location: 5,
+                    offset: SNAKE_COLOR_OFFSET,
+                    format: Mesh::ATTRIBUTE_COLOR.format,
+                },
+            ]
+            .into(),
+        },
+    ))
+}
+
 // used only to get it's sideof
 #[derive(ShaderType)]
 #[repr(C)]
@@ -299,6 +426,20 @@ pub struct DrawIndexedIndirect {
     first_instance: u32,
 }
 
+// the shader drops vertices past this, enough for the surface of a MAX_GRID_RESOLUTION grid
+pub const SNAKE_MAX_VERTICES: u64 = 1024 * 96;
+pub const SNAKE_VERTEX_BUFFER_SIZE: u64 = SNAKE_VERTEX_STRIDE * SNAKE_MAX_VERTICES;
+pub const SNAKE_INDEX_BUFFER_SIZE: u64 = 1024 * 1024;
+// CellInfo { vbo_index: u32, intersections_bitmask: u32 } for every grid cell
+pub const SNAKE_CELL_INFO_SIZE: u64 = 2 * 4;
+pub const SNAKE_CELL_BUFFER_SIZE: u64 = SNAKE_CELL_INFO_SIZE * (MAX_GRID_RESOLUTION as u64).pow(3);
+// vertex and index counters
+pub const SNAKE_ATOMICS_BUFFER_SIZE: u64 = 2 * 4;
+// vec4<f32> for every grid cell
+pub const SNAKE_RELAXED_POSITION_SIZE: u64 = 4 * 4;
+pub const SNAKE_RELAXED_BUFFER_SIZE: u64 =
+    SNAKE_RELAXED_POSITION_SIZE * (MAX_GRID_RESOLUTION as u64).pow(3);
+
 pub fn create_snake_buffers(
     render_device: Res<RenderDevice>,
     mut snake_mesh_instances: ResMut<SnakeMeshInstances>,
@@ -308,7 +449,7 @@ pub fn create_snake_buffers(
         if snake.vertex_buffer.is_none() {
             let vertex_buffer = render_device.create_buffer(&BufferDescriptor {
                 label: Some("snake vertex buffer"),
-                size: 1024 * 256,
+                size: SNAKE_VERTEX_BUFFER_SIZE,
                 usage: BufferUsages::VERTEX | BufferUsages::STORAGE,
                 mapped_at_creation: false,
             });
@@ -319,7 +460,7 @@ pub fn create_snake_buffers(
         if snake.index_buffer.is_none() {
             let index_buffer = render_device.create_buffer(&BufferDescriptor {
                 label: Some("snake index buffer"),
-                size: 1024 * 256,
+                size: SNAKE_INDEX_BUFFER_SIZE,
                 usage: BufferUsages::INDEX | BufferUsages::STORAGE,
                 mapped_at_creation: false,
             });
@@ -330,13 +471,24 @@ pub fn create_snake_buffers(
         if snake.cell_buffer.is_none() {
             let cells_buffer = render_device.create_buffer(&BufferDescriptor {
                 label: Some("Snake cells buffer"),
-                size: 1024 * 256,
+                size: SNAKE_CELL_BUFFER_SIZE,
                 usage: BufferUsages::STORAGE,
                 mapped_at_creation: false,
             });
             snake.cell_buffer = Some(cells_buffer);
         }
 
+        // relaxed positions
+        if snake.relaxed_buffer.is_none() {
+            let relaxed_buffer = render_device.create_buffer(&BufferDescriptor {
+                label: Some("Snake relaxed positions buffer"),
+                size: SNAKE_RELAXED_BUFFER_SIZE,
+                usage: BufferUsages::STORAGE,
+                mapped_at_creation: false,
+            });
+            snake.relaxed_buffer = Some(relaxed_buffer);
+        }
+
         // uniform
         // TODO: write new values instead of recreating this 2 buffers
         let uniform_buffer = render_device.create_buffer_with_data(&BufferInitDescriptor {
@@ -346,10 +498,18 @@ pub fn create_snake_buffers(
         });
         snake.uniform_buffer = Some(uniform_buffer);
 
+        let previous_uniform_buffer =
+            render_device.create_buffer_with_data(&BufferInitDescriptor {
+                label: Some("Snake previous uniform buffer"),
+                contents: bytemuck::bytes_of(&snake.previous_uniforms),
+                usage: BufferUsages::UNIFORM,
+            });
+        snake.previous_uniform_buffer = Some(previous_uniform_buffer);
+
         // atomics
         let atomics_buffer = render_device.create_buffer_with_data(&BufferInitDescriptor {
             label: Some("Snake atomics buffer"),
-            contents: bytemuck::bytes_of(&[0.0, 0.0]),
+            contents: bytemuck::bytes_of(&[0u32; 2]),
             usage: BufferUsages::STORAGE,
         });
         snake.atomics_buffer = Some(atomics_buffer);
@@ -400,6 +560,14 @@ pub fn prepare_snake_compute_bind_groups(
             error!("Snake indirect buffer is None");
             return;
         };
+        let Some(previous_uniform_buffer) = snake.previous_uniform_buffer.as_ref() else {
+            error!("Snake previous uniform buffer is None");
+            return;
+        };
+        let Some(relaxed_buffer) = snake.relaxed_buffer.as_ref() else {
+            error!("Snake relaxed positions buffer is None");
+            return;
+        };
 
         let bind_group = render_device.create_bind_group(
             None,
@@ -429,6 +597,14 @@ pub fn prepare_snake_compute_bind_groups(
                     binding: 5,
                     resource: indirect_buffer.as_entire_binding(),
                 },
+                BindGroupEntry {
+                    binding: 6,
+                    resource: previous_uniform_buffer.as_entire_binding(),
+                },
+                BindGroupEntry {
+                    binding: 7,
+                    resource: relaxed_buffer.as_entire_binding(),
+                },
             ],
         );
         snake.compute_bind_group = Some(bind_group);
@@ -451,7 +627,9 @@ pub fn extract_snakes(
         )>,
     >,
 ) {
-    snake_mesh_instances.clear();
+    // instances are rebuilt every frame, last frame's uniforms are kept around so the compute
+    // shader can reconstruct where each vertex was for motion vectors
+    let previous_instances = std::mem::take(&mut **snake_mesh_instances);
     for (
         entity,
         snake_mesh,
@@ -463,8 +641,10 @@ pub fn extract_snakes(
         transmitted_receiver,
     ) in snake_query.iter()
     {
+        // not visible from any view, per view visibility and render layers are handled by
+        // VisibleEntities when queueing
         if !view_visibility.get() {
-            return;
+            continue;
         }
         let transform = transform.affine();
         let previous_transform = previous_transform.map(|t| t.0).unwrap_or(transform);
@@ -484,21 +664,30 @@ pub fn extract_snakes(
             previous_transform: (&previous_transform).into(),
             flags: flags.bits(),
         };
+        let uniforms = SnakeMeshUniforms::new(
+            polygonization_settings.grid_size,
+            polygonization_settings.grid_origin,
+            &snake_mesh.sdf(),
+        );
+        let previous_uniforms = previous_instances
+            .get(&entity)
+            .map(|previous| previous.uniforms)
+            .unwrap_or(uniforms);
         commands.get_or_spawn(entity);
         snake_mesh_instances.insert(
             entity,
             SnakeMeshInstance {
                 fake_mesh_asset: snake_mesh.fake_mesh_asset,
-                uniforms: SnakeMeshUniforms::new(
-                    polygonization_settings.grid_size,
-                    polygonization_settings.grid_origin,
-                    snake_mesh.center,
-                    snake_mesh.radius,
-                ),
+                uniforms,
+                previous_uniforms,
+                resolution: polygonization_settings.resolution,
+                relaxation_iterations: polygonization_settings.relaxation_iterations,
                 vertex_buffer: None,
                 index_buffer: None,
                 cell_buffer: None,
+                relaxed_buffer: None,
                 uniform_buffer: None,
+                previous_uniform_buffer: None,
                 atomics_buffer: None,
                 indirect_buffer: None,
                 compute_bind_group: None,
@@ -0,0 +1,283 @@
+use std::mem::{offset_of, size_of};
+
+use bevy::render::render_resource::{BindGroupLayoutEntry, BindingType, BufferBindingType};
+use naga::{
+    proc::Layouter, AddressSpace, Expression, Handle, Literal, Module, StorageAccess, Type,
+    TypeInner,
+};
+
+use super::{
+    gpu_systems::{DrawIndexedIndirect, SNAKE_CELL_INFO_SIZE, SNAKE_VERTEX_STRIDE},
+    resources::SnakeMeshUniforms,
+    sdf::{SnakeEyesUniform, SnakeSdfUniform, MAX_BODY_POINTS, RADIUS_SCALE_VECTORS},
+};
+
+const SDF_SHADER: &str = include_str!("../../assets/shaders/snake_sdf.wgsl");
+const COMPUTE_SHADER: &str = include_str!("../../assets/shaders/snake_compute.wgsl");
+
+// Checks the rust side of the compute pass against snake_compute.wgsl, returns every mismatch
+pub fn validate_compute_layout(entries: &[BindGroupLayoutEntry]) -> Result<(), Vec<String>> {
+    let module = parse_compute_shader().map_err(|error| vec![error])?;
+    let mut layouter = Layouter::default();
+    layouter
+        .update(module.to_ctx())
+        .map_err(|error| vec![error.to_string()])?;
+
+    let mut mismatches = Vec::new();
+    check_struct(
+        &module,
+        &layouter,
+        "PolygonizationInfo",
+        size_of::<SnakeMeshUniforms>(),
+        &[
+            ("grid_size", offset_of!(SnakeMeshUniforms, grid_size)),
+            ("grid_origin", offset_of!(SnakeMeshUniforms, grid_origin)),
+            ("snake", offset_of!(SnakeMeshUniforms, snake)),
+        ],
+        &mut mismatches,
+    );
+    check_struct(
+        &module,
+        &layouter,
+        "SnakeSdf",
+        size_of::<SnakeSdfUniform>(),
+        &[
+            ("radius", offset_of!(SnakeSdfUniform, radius)),
+            ("point_count", offset_of!(SnakeSdfUniform, point_count)),
+            ("points", offset_of!(SnakeSdfUniform, points)),
+            ("radius_scales", offset_of!(SnakeSdfUniform, radius_scales)),
+            ("colors", offset_of!(SnakeSdfUniform, colors)),
+            ("eyes", offset_of!(SnakeSdfUniform, eyes)),
+            ("dissolve", offset_of!(SnakeSdfUniform, dissolve)),
+        ],
+        &mut mismatches,
+    );
+    check_struct(
+        &module,
+        &layouter,
+        "SnakeEyes",
+        size_of::<SnakeEyesUniform>(),
+        &[
+            ("left", offset_of!(SnakeEyesUniform, left)),
+            ("right", offset_of!(SnakeEyesUniform, right)),
+            ("openness", offset_of!(SnakeEyesUniform, openness)),
+            ("smoothness", offset_of!(SnakeEyesUniform, smoothness)),
+        ],
+        &mut mismatches,
+    );
+    check_struct(
+        &module,
+        &layouter,
+        "CellInfo",
+        SNAKE_CELL_INFO_SIZE as usize,
+        &[],
+        &mut mismatches,
+    );
+    check_struct(
+        &module,
+        &layouter,
+        "DrawIndexedIndirect",
+        size_of::<DrawIndexedIndirect>(),
+        &[],
+        &mut mismatches,
+    );
+    check_constant(
+        &module,
+        "MAX_BODY_POINTS",
+        MAX_BODY_POINTS as u32,
+        &mut mismatches,
+    );
+    check_constant(
+        &module,
+        "RADIUS_SCALE_VECTORS",
+        RADIUS_SCALE_VECTORS as u32,
+        &mut mismatches,
+    );
+    // in f32s
+    check_constant(
+        &module,
+        "VERTEX_STRIDE",
+        SNAKE_VERTEX_STRIDE as u32 / 4,
+        &mut mismatches,
+    );
+    check_bindings(&module, &layouter, entries, &mut mismatches);
+
+    if mismatches.is_empty() {
+        Ok(())
+    } else {
+        Err(mismatches)
+    }
+}
+
+// naga doesn't know about naga_oil imports, so the sdf module is pasted in front
+fn parse_compute_shader() -> Result<Module, String> {
+    let source = [SDF_SHADER, COMPUTE_SHADER]
+        .iter()
+        .flat_map(|shader| shader.lines())
+        .filter(|line| !line.starts_with('#'))
+        .collect::<Vec<_>>()
+        .join("\n");
+    naga::front::wgsl::parse_str(&source).map_err(|error| error.emit_to_string(&source))
+}
+
+fn find_type(module: &Module, name: &str) -> Option<Handle<Type>> {
+    module
+        .types
+        .iter()
+        .find(|(_, ty)| ty.name.as_deref() == Some(name))
+        .map(|(handle, _)| handle)
+}
+
+fn check_struct(
+    module: &Module,
+    layouter: &Layouter,
+    name: &str,
+    size: usize,
+    fields: &[(&str, usize)],
+    mismatches: &mut Vec<String>,
+) {
+    let Some(handle) = find_type(module, name) else {
+        mismatches.push(format!("struct {name} is missing in the shader"));
+        return;
+    };
+    let TypeInner::Struct { members, .. } = &module.types[handle].inner else {
+        mismatches.push(format!("{name} is not a struct in the shader"));
+        return;
+    };
+    let shader_size = layouter[handle].size as usize;
+    if shader_size != size {
+        mismatches.push(format!(
+            "{name} is {shader_size} bytes in the shader, {size} bytes in rust"
+        ));
+    }
+    for (field, offset) in fields {
+        match members
+            .iter()
+            .find(|member| member.name.as_deref() == Some(field))
+        {
+            Some(member) if member.offset as usize != *offset => mismatches.push(format!(
+                "{name}.{field} is at offset {} in the shader, {offset} in rust",
+                member.offset
+            )),
+            Some(_) => {}
+            None => mismatches.push(format!("{name}.{field} is missing in the shader")),
+        }
+    }
+}
+
+fn check_constant(module: &Module, name: &str, value: u32, mismatches: &mut Vec<String>) {
+    let constant = module
+        .constants
+        .iter()
+        .find(|(_, constant)| constant.name.as_deref() == Some(name));
+    let Some((_, constant)) = constant else {
+        mismatches.push(format!("constant {name} is missing in the shader"));
+        return;
+    };
+    match module.const_expressions[constant.init] {
+        Expression::Literal(Literal::U32(shader_value)) if shader_value == value => {}
+        ref expression => mismatches.push(format!(
+            "constant {name} is {expression:?} in the shader, {value} in rust"
+        )),
+    }
+}
+
+fn check_bindings(
+    module: &Module,
+    layouter: &Layouter,
+    entries: &[BindGroupLayoutEntry],
+    mismatches: &mut Vec<String>,
+) {
+    for (_, variable) in module.global_variables.iter() {
+        let Some(binding) = &variable.binding else {
+            continue;
+        };
+        let name = variable.name.as_deref().unwrap_or("unnamed");
+        let Some(entry) = entries
+            .iter()
+            .find(|entry| binding.group == 0 && entry.binding == binding.binding)
+        else {
+            mismatches.push(format!(
+                "{name} (group {} binding {}) is not in the bind group layout",
+                binding.group, binding.binding
+            ));
+            continue;
+        };
+        let BindingType::Buffer {
+            ty,
+            min_binding_size,
+            ..
+        } = entry.ty
+        else {
+            mismatches.push(format!("binding {} is not a buffer", entry.binding));
+            continue;
+        };
+
+        let space_matches = match (ty, variable.space) {
+            (BufferBindingType::Uniform, AddressSpace::Uniform) => true,
+            (BufferBindingType::Storage { read_only }, AddressSpace::Storage { access }) => {
+                read_only != access.contains(StorageAccess::STORE)
+            }
+            _ => false,
+        };
+        if !space_matches {
+            mismatches.push(format!(
+                "{name} is {:?} in the shader, {ty:?} in the layout",
+                variable.space
+            ));
+        }
+
+        let shader_size = layouter[variable.ty].size as u64;
+        let layout_size = min_binding_size.map_or(0, |size| size.get());
+        let size_matches = match ty {
+            BufferBindingType::Uniform => layout_size == shader_size,
+            // runtime sized arrays only need room for a single element
+            _ => layout_size >= shader_size,
+        };
+        if !size_matches {
+            mismatches.push(format!(
+                "{name} needs {shader_size} bytes in the shader, layout min size is {layout_size}"
+            ));
+        }
+    }
+
+    for entry in entries {
+        let in_shader = module.global_variables.iter().any(|(_, variable)| {
+            variable
+                .binding
+                .as_ref()
+                .is_some_and(|binding| binding.group == 0 && binding.binding == entry.binding)
+        });
+        if !in_shader {
+            mismatches.push(format!(
+                "binding {} is missing in the shader",
+                entry.binding
+            ));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::validate_compute_layout;
+    use crate::snake_mesh::pipelines::snake_compute_bind_group_layout_entries;
+    use bevy::render::render_resource::{BindingType, BufferBindingType};
+
+    #[test]
+    fn test_compute_layout_matches_shader() {
+        let entries = snake_compute_bind_group_layout_entries();
+        if let Err(mismatches) = validate_compute_layout(&entries) {
+            panic!("{}", mismatches.join("\n"));
+        }
+    }
+
+    #[test]
+    fn test_mismatch_is_reported() {
+        let mut entries = snake_compute_bind_group_layout_entries().to_vec();
+        if let BindingType::Buffer { ty, .. } = &mut entries[0].ty {
+            *ty = BufferBindingType::Storage { read_only: true };
+        }
+        let mismatches = validate_compute_layout(&entries).unwrap_err();
+        assert_eq!(mismatches.len(), 1);
+    }
+}
@@ -1,75 +1,233 @@
+mod colors;
 mod components;
+mod cpu_polygonizer;
+mod debug_material;
 mod draw_command;
+mod events;
 mod gpu_systems;
+mod layout_validation;
 mod node;
 mod pipelines;
+mod profile;
 mod resources;
+mod sdf;
+mod systems;
+
+use std::{hash::Hash, marker::PhantomData};
 
 use bevy::{
-    core_pipeline::core_3d::{
-        graph::{Core3d, Node3d},
-        AlphaMask3d, Opaque3d, Transmissive3d, Transparent3d,
+    core_pipeline::{
+        core_3d::{
+            graph::{Core3d, Node3d},
+            AlphaMask3d, Opaque3d, Transmissive3d, Transparent3d,
+        },
+        prepass::Opaque3dPrepass,
+    },
+    pbr::{
+        wireframe::{WireframeMaterial, WireframePlugin},
+        PrepassPipeline,
     },
     prelude::*,
     render::{
-        batching::batch_and_prepare_render_phase, render_graph::RenderGraphApp,
-        render_phase::AddRenderCommand, render_resource::SpecializedMeshPipelines, Render,
-        RenderApp, RenderSet,
+        batching::batch_and_prepare_render_phase,
+        render_graph::RenderGraphApp,
+        render_phase::AddRenderCommand,
+        render_resource::{DownlevelFlags, SpecializedMeshPipelines},
+        renderer::RenderAdapter,
+        view::{calculate_bounds, VisibilitySystems},
+        Render, RenderApp, RenderSet,
     },
 };
 
-pub use components::{PolygonizationSettings, SnakeMesh};
+pub use colors::{SegmentTint, SnakeColors};
+pub use components::{
+    DeathStyle, PolygonizationSettings, PreyBulgeSettings, SnakeDead, SnakeDeath, SnakeDebugView,
+    SnakeMesh, MAX_GRID_RESOLUTION, MIN_GRID_RESOLUTION,
+};
+pub use debug_material::SnakeDebugMaterial;
+pub use events::{PreySwallowed, SnakeDied};
+pub use profile::RadiusProfile;
+pub use sdf::{Sdf, SnakeDissolve, SnakeEyes, SnakeSdf, MAX_BODY_POINTS};
 
 use node::{SnakeComputeNode, SnakeComputeNodeLabel};
 
+// SnakeDied is sent and SnakeDeath replaced by SnakeDead in this set
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SnakeDeathSet;
+
 #[derive(Default)]
 pub struct SnakeMeshPlugin;
 
 impl Plugin for SnakeMeshPlugin {
     fn build(&self, app: &mut App) {
+        app.add_plugins((
+            MaterialPlugin::<SnakeDebugMaterial>::default(),
+            SnakeMaterialPlugin::<StandardMaterial> {
+                prepass_enabled: true,
+                ..default()
+            },
+            SnakeMaterialPlugin::<SnakeDebugMaterial>::default(),
+        ))
+        .add_event::<PreySwallowed>()
+        .add_event::<SnakeDied>()
+        .add_systems(
+            Update,
+            (
+                (systems::spawn_prey_bulges, systems::move_prey_bulges).chain(),
+                systems::animate_deaths.in_set(SnakeDeathSet),
+            ),
+        )
+        .add_systems(
+            PostUpdate,
+            (
+                systems::apply_snake_debug_view,
+                // overrides the bounds bevy computes for the cpu polygonized mesh
+                systems::update_snake_aabb
+                    .in_set(VisibilitySystems::CalculateBounds)
+                    .after(calculate_bounds),
+            ),
+        )
+        .register_type::<SnakeDebugView>()
+        .register_type::<RadiusProfile>()
+        .register_type::<SnakeColors>()
+        .register_type::<PreyBulgeSettings>()
+        .register_type::<SnakeDeath>()
+        .register_type::<SnakeDead>();
+
         app.sub_app_mut(RenderApp)
-            .add_systems(ExtractSchedule, (gpu_systems::extract_snakes,))
+            .add_systems(
+                ExtractSchedule,
+                (gpu_systems::extract_snakes,)
+                    .run_if(resource_exists::<pipelines::SnakeComputePipeline>),
+            )
             .add_systems(
                 Render,
                 (
-                    gpu_systems::queue_material_snakes::<StandardMaterial>.in_set(RenderSet::Queue),
                     gpu_systems::create_snake_buffers.in_set(RenderSet::PrepareResources),
+                    gpu_systems::prepare_snake_compute_bind_groups
+                        .in_set(RenderSet::PrepareBindGroups)
+                        .run_if(resource_exists::<pipelines::SnakeComputePipeline>),
+                ),
+            )
+            .init_resource::<resources::SnakeMeshInstances>()
+            .add_render_graph_node::<SnakeComputeNode>(Core3d, SnakeComputeNodeLabel)
+            .add_render_graph_edge(Core3d, SnakeComputeNodeLabel, Node3d::Prepass)
+            .add_render_graph_edge(Core3d, SnakeComputeNodeLabel, Node3d::StartMainPass);
+    }
+
+    fn finish(&self, app: &mut App) {
+        // every plugin is built by now, so WireframePlugin can be added in any order
+        if app.is_plugin_added::<WireframePlugin>() {
+            SnakeMaterialPlugin::<WireframeMaterial>::default().build(app);
+            app.add_systems(PostUpdate, systems::apply_snake_wireframe_material);
+        }
+
+        let render_app = app.sub_app_mut(RenderApp);
+        let supports_compute = render_app
+            .world
+            .resource::<RenderAdapter>()
+            .get_downlevel_capabilities()
+            .flags
+            .contains(DownlevelFlags::COMPUTE_SHADERS | DownlevelFlags::INDIRECT_EXECUTION);
+
+        if supports_compute {
+            render_app.init_resource::<pipelines::SnakeComputePipeline>();
+        } else {
+            warn!("compute shaders or indirect draws are not supported, polygonizing snakes on the cpu");
+            app.add_systems(
+                PostUpdate,
+                (
+                    cpu_polygonizer::queue_cpu_polygonization,
+                    cpu_polygonizer::apply_cpu_polygonization,
+                )
+                    .chain(),
+            );
+        }
+    }
+
+    // the wireframe MaterialPipeline is only there once MaterialPlugin::finish ran, which can be
+    // after the finish of this plugin
+    fn cleanup(&self, app: &mut App) {
+        if app.is_plugin_added::<WireframePlugin>() {
+            SnakeMaterialPlugin::<WireframeMaterial>::default().finish(app);
+        }
+    }
+}
+
+// Renders snakes with material M, like MaterialPlugin does for regular meshes
+pub struct SnakeMaterialPlugin<M: Material> {
+    pub prepass_enabled: bool,
+    pub _marker: PhantomData<M>,
+}
+
+impl<M: Material> Default for SnakeMaterialPlugin<M> {
+    fn default() -> Self {
+        Self {
+            prepass_enabled: false,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<M: Material> Plugin for SnakeMaterialPlugin<M>
+where
+    M::Data: PartialEq + Eq + Hash + Clone,
+{
+    fn build(&self, app: &mut App) {
+        app.sub_app_mut(RenderApp)
+            .add_systems(
+                Render,
+                (
+                    gpu_systems::queue_material_snakes::<M>.in_set(RenderSet::Queue),
                     (
                         batch_and_prepare_render_phase::<
                             Transmissive3d,
-                            pipelines::SnakeMaterialPipeline<StandardMaterial>,
+                            pipelines::SnakeMaterialPipeline<M>,
                         >,
                         batch_and_prepare_render_phase::<
                             Transparent3d,
-                            pipelines::SnakeMaterialPipeline<StandardMaterial>,
+                            pipelines::SnakeMaterialPipeline<M>,
                         >,
                         batch_and_prepare_render_phase::<
                             Opaque3d,
-                            pipelines::SnakeMaterialPipeline<StandardMaterial>,
+                            pipelines::SnakeMaterialPipeline<M>,
                         >,
                         batch_and_prepare_render_phase::<
                             AlphaMask3d,
-                            pipelines::SnakeMaterialPipeline<StandardMaterial>,
+                            pipelines::SnakeMaterialPipeline<M>,
                         >,
                     )
                         .in_set(RenderSet::PrepareResources),
-                    gpu_systems::prepare_snake_compute_bind_groups
-                        .in_set(RenderSet::PrepareBindGroups),
                 ),
             )
-            .add_render_command::<Transmissive3d, draw_command::DrawSnake<StandardMaterial>>()
-            .add_render_command::<Transparent3d, draw_command::DrawSnake<StandardMaterial>>()
-            .add_render_command::<Opaque3d, draw_command::DrawSnake<StandardMaterial>>()
-            .add_render_command::<AlphaMask3d, draw_command::DrawSnake<StandardMaterial>>()
-            .init_resource::<SpecializedMeshPipelines<pipelines::SnakeMaterialPipeline<StandardMaterial>>>()
-            .init_resource::<resources::SnakeMeshInstances>()
-            .add_render_graph_node::<SnakeComputeNode>(Core3d, SnakeComputeNodeLabel)
-            .add_render_graph_edge(Core3d, SnakeComputeNodeLabel, Node3d::StartMainPass);
+            .add_render_command::<Transmissive3d, draw_command::DrawSnake<M>>()
+            .add_render_command::<Transparent3d, draw_command::DrawSnake<M>>()
+            .add_render_command::<Opaque3d, draw_command::DrawSnake<M>>()
+            .add_render_command::<AlphaMask3d, draw_command::DrawSnake<M>>()
+            .init_resource::<SpecializedMeshPipelines<pipelines::SnakeMaterialPipeline<M>>>();
     }
 
     fn finish(&self, app: &mut App) {
-        app.sub_app_mut(RenderApp)
-            .init_resource::<pipelines::SnakeMaterialPipeline<StandardMaterial>>()
-            .init_resource::<pipelines::SnakeComputePipeline>();
+        let render_app = app.sub_app_mut(RenderApp);
+        render_app.init_resource::<pipelines::SnakeMaterialPipeline<M>>();
+
+        // PrepassPipeline only exists if the material has prepass or shadows enabled
+        if self.prepass_enabled && render_app.world.contains_resource::<PrepassPipeline<M>>() {
+            render_app
+                .init_resource::<pipelines::SnakePrepassPipeline<M>>()
+                .init_resource::<SpecializedMeshPipelines<pipelines::SnakePrepassPipeline<M>>>()
+                .add_render_command::<Opaque3dPrepass, draw_command::DrawSnakePrepass<M>>()
+                .add_systems(
+                    Render,
+                    (
+                        gpu_systems::queue_prepass_snakes::<M>.in_set(RenderSet::Queue),
+                        batch_and_prepare_render_phase::<
+                            Opaque3dPrepass,
+                            pipelines::SnakePrepassPipeline<M>,
+                        >
+                            .in_set(RenderSet::PrepareResources),
+                    ),
+                );
+        }
     }
 }
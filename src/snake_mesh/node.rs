@@ -9,6 +9,8 @@ use bevy::{
 
 use super::{pipelines::SnakeComputePipeline, resources::SnakeMeshInstances};
 
+const WORKGROUP_SIZE: u32 = 8;
+
 #[derive(Default)]
 pub struct SnakeComputeNode;
 
@@ -22,7 +24,10 @@ impl render_graph::Node for SnakeComputeNode {
         render_context: &mut RenderContext,
         world: &World,
     ) -> Result<(), render_graph::NodeRunError> {
-        let compute_pipeline = world.resource::<SnakeComputePipeline>();
+        // missing when snakes are polygonized on the cpu
+        let Some(compute_pipeline) = world.get_resource::<SnakeComputePipeline>() else {
+            return Ok(());
+        };
         let pipeline_cache = world.resource::<PipelineCache>();
 
         let Some(find_vertices_pipeline) = pipeline_cache.get_compute_pipeline(compute_pipeline.find_vertices_pipeline) else {
@@ -31,6 +36,12 @@ impl render_graph::Node for SnakeComputeNode {
         let Some(connect_vertices_pipeline) = pipeline_cache.get_compute_pipeline(compute_pipeline.connect_vertices_pipeline) else {
             return Ok(());
         };
+        let Some(relax_vertices_pipeline) = pipeline_cache.get_compute_pipeline(compute_pipeline.relax_vertices_pipeline) else {
+            return Ok(());
+        };
+        let Some(apply_relaxed_vertices_pipeline) = pipeline_cache.get_compute_pipeline(compute_pipeline.apply_relaxed_vertices_pipeline) else {
+            return Ok(());
+        };
         let Some(prepare_indirect_buffer_pipeline) = pipeline_cache.get_compute_pipeline(compute_pipeline.prepare_indirect_buffer_pipeline) else {
             return Ok(());
         };
@@ -39,15 +50,23 @@ impl render_graph::Node for SnakeComputeNode {
 
         let snakes = world.resource::<SnakeMeshInstances>();
         for (_, snake) in snakes.iter() {
+            // the shader reads the grid resolution back from the dispatch size
+            let workgroups = snake.resolution / WORKGROUP_SIZE;
             let Some(bind_group) = snake.compute_bind_group.as_ref() else {
                 error!("missing snake compute bind group");
                 return Ok(());
             };
             pass.set_bind_group(0, bind_group, &[]);
             pass.set_pipeline(find_vertices_pipeline);
-            pass.dispatch_workgroups(4, 4, 4);
+            pass.dispatch_workgroups(workgroups, workgroups, workgroups);
             pass.set_pipeline(connect_vertices_pipeline);
-            pass.dispatch_workgroups(4, 4, 4);
+            pass.dispatch_workgroups(workgroups, workgroups, workgroups);
+            for _ in 0..snake.relaxation_iterations {
+                pass.set_pipeline(relax_vertices_pipeline);
+                pass.dispatch_workgroups(workgroups, workgroups, workgroups);
+                pass.set_pipeline(apply_relaxed_vertices_pipeline);
+                pass.dispatch_workgroups(workgroups, workgroups, workgroups);
+            }
             pass.set_pipeline(prepare_indirect_buffer_pipeline);
             pass.dispatch_workgroups(1, 1, 1);
         }
@@ -1,6 +1,6 @@
 use bevy::{
     ecs::system::{lifetimeless::SRes, SystemParamItem},
-    pbr::{MaterialPipeline, MaterialPipelineKey, MeshUniform},
+    pbr::{MaterialPipeline, MaterialPipelineKey, MeshUniform, PrepassPipeline},
     prelude::*,
     render::{
         batching::GetBatchData,
@@ -8,7 +8,7 @@ use bevy::{
         render_resource::{
             binding_types, BindGroupLayout, BindGroupLayoutEntries, CachedComputePipelineId,
             ComputePipelineDescriptor, PipelineCache, RenderPipelineDescriptor, ShaderStages,
-            SpecializedMeshPipeline, SpecializedMeshPipelineError,
+            SpecializedMeshPipeline, SpecializedMeshPipelineError, VertexAttribute, VertexFormat,
         },
         renderer::RenderDevice,
     },
@@ -18,18 +18,37 @@ use std::{borrow::Cow, hash::Hash, num::NonZeroU64};
 
 use crate::snake_mesh::resources::SnakeMeshUniforms;
 
-use super::{gpu_systems::DrawIndexedIndirect, resources::SnakeMeshInstances};
+use super::{
+    gpu_systems::{
+        DrawIndexedIndirect, SNAKE_ATOMICS_BUFFER_SIZE, SNAKE_CELL_BUFFER_SIZE,
+        SNAKE_INDEX_BUFFER_SIZE, SNAKE_NORMAL_OFFSET, SNAKE_NORMAL_SHADER_LOCATION,
+        SNAKE_PREVIOUS_POSITION_OFFSET, SNAKE_PREVIOUS_POSITION_SHADER_LOCATION,
+        SNAKE_RELAXED_BUFFER_SIZE, SNAKE_VERTEX_BUFFER_SIZE,
+    },
+    layout_validation,
+    resources::SnakeMeshInstances,
+};
 
 #[derive(Resource)]
 pub struct SnakeMaterialPipeline<M: Material> {
     material_pipeline: MaterialPipeline<M>,
 }
 
+#[derive(Resource)]
+pub struct SnakePrepassPipeline<M: Material> {
+    prepass_pipeline: PrepassPipeline<M>,
+    vertex_shader: Handle<Shader>,
+}
+
 #[derive(Resource)]
 pub struct SnakeComputePipeline {
     pub compute_bind_group_layout: BindGroupLayout,
+    // kept alive so snake_compute.wgsl can import it
+    pub sdf_shader: Handle<Shader>,
     pub find_vertices_pipeline: CachedComputePipelineId,
     pub connect_vertices_pipeline: CachedComputePipelineId,
+    pub relax_vertices_pipeline: CachedComputePipelineId,
+    pub apply_relaxed_vertices_pipeline: CachedComputePipelineId,
     pub prepare_indirect_buffer_pipeline: CachedComputePipelineId,
 }
 
@@ -94,6 +113,68 @@ where
     }
 }
 
+impl<M: Material> SpecializedMeshPipeline for SnakePrepassPipeline<M>
+where
+    M::Data: PartialEq + Eq + Hash + Clone,
+{
+    type Key = SnakeMaterialPipelineKey<M>;
+
+    fn specialize(
+        &self,
+        key: Self::Key,
+        layout: &MeshVertexBufferLayout,
+    ) -> Result<RenderPipelineDescriptor, SpecializedMeshPipelineError> {
+        let mut descriptor = self
+            .prepass_pipeline
+            .specialize(key.material_pipeline_key, layout)?;
+        // snake vertices are regenerated every frame, so the default prepass vertex shader can't
+        // get the previous position from the previous model matrix alone
+        descriptor.vertex.shader = self.vertex_shader.clone();
+        descriptor.vertex.entry_point = Cow::from("vertex");
+        if let Some(buffer) = descriptor.vertex.buffers.first_mut() {
+            buffer.attributes.push(VertexAttribute {
+                format: VertexFormat::Float32x3,
+                offset: SNAKE_PREVIOUS_POSITION_OFFSET,
+                shader_location: SNAKE_PREVIOUS_POSITION_SHADER_LOCATION,
+            });
+            buffer.attributes.push(VertexAttribute {
+                format: VertexFormat::Float32x3,
+                offset: SNAKE_NORMAL_OFFSET,
+                shader_location: SNAKE_NORMAL_SHADER_LOCATION,
+            });
+        }
+        Ok(descriptor)
+    }
+}
+
+impl<M: Material> GetBatchData for SnakePrepassPipeline<M> {
+    type Param = SRes<SnakeMeshInstances>;
+    type CompareData = ();
+
+    type BufferData = MeshUniform;
+
+    fn get_batch_data(
+        snake_instances: &SystemParamItem<Self::Param>,
+        entity: Entity,
+    ) -> Option<(Self::BufferData, Option<Self::CompareData>)> {
+        let snake = snake_instances.get(&entity)?;
+        Some((MeshUniform::new(&snake.transforms, None), None))
+    }
+}
+
+impl<M: Material> FromWorld for SnakePrepassPipeline<M> {
+    fn from_world(world: &mut World) -> Self {
+        let prepass_pipeline = PrepassPipeline::<M>::from_world(world);
+        let vertex_shader = world
+            .resource::<AssetServer>()
+            .load("shaders/snake_prepass.wgsl");
+        Self {
+            prepass_pipeline,
+            vertex_shader,
+        }
+    }
+}
+
 impl<M: Material> GetBatchData for SnakeMaterialPipeline<M> {
     type Param = SRes<SnakeMeshInstances>;
     type CompareData = ();
@@ -118,31 +199,49 @@ impl<M: Material> FromWorld for SnakeMaterialPipeline<M> {
     }
 }
 
+pub fn snake_compute_bind_group_layout_entries() -> BindGroupLayoutEntries<8> {
+    BindGroupLayoutEntries::sequential(
+        ShaderStages::COMPUTE,
+        (
+            // Uniforms
+            binding_types::uniform_buffer::<SnakeMeshUniforms>(false),
+            // VBO
+            binding_types::storage_buffer_sized(false, NonZeroU64::new(SNAKE_VERTEX_BUFFER_SIZE)),
+            // IBO
+            binding_types::storage_buffer_sized(false, NonZeroU64::new(SNAKE_INDEX_BUFFER_SIZE)),
+            // Cells, Intermediate buffer
+            binding_types::storage_buffer_sized(false, NonZeroU64::new(SNAKE_CELL_BUFFER_SIZE)),
+            // Atomics
+            binding_types::storage_buffer_sized(false, NonZeroU64::new(SNAKE_ATOMICS_BUFFER_SIZE)),
+            // indirect
+            binding_types::storage_buffer::<DrawIndexedIndirect>(false),
+            // Previous frame uniforms
+            binding_types::uniform_buffer::<SnakeMeshUniforms>(false),
+            // Relaxed positions, Intermediate buffer
+            binding_types::storage_buffer_sized(false, NonZeroU64::new(SNAKE_RELAXED_BUFFER_SIZE)),
+        ),
+    )
+}
+
 impl FromWorld for SnakeComputePipeline {
     fn from_world(world: &mut World) -> Self {
         let render_device = world.resource::<RenderDevice>();
 
-        let compute_bind_group_layout = render_device.create_bind_group_layout(
-            "snake compute bind group layout",
-            &BindGroupLayoutEntries::sequential(
-                ShaderStages::COMPUTE,
-                (
-                    // Uniforms
-                    binding_types::uniform_buffer::<SnakeMeshUniforms>(false),
-                    // VBO
-                    binding_types::storage_buffer_sized(false, NonZeroU64::new(1024)),
-                    // IBO
-                    binding_types::storage_buffer_sized(false, NonZeroU64::new(1024)),
-                    // Cells, Intermediate buffer
-                    binding_types::storage_buffer_sized(false, NonZeroU64::new(1024)),
-                    // Atomics
-                    binding_types::storage_buffer_sized(false, None),
-                    // indirect
-                    binding_types::storage_buffer::<DrawIndexedIndirect>(false),
-                ),
-            ),
-        );
+        let entries = snake_compute_bind_group_layout_entries();
+        if cfg!(debug_assertions) {
+            if let Err(mismatches) = layout_validation::validate_compute_layout(&entries) {
+                panic!(
+                    "snake compute layout doesn't match snake_compute.wgsl:\n{}",
+                    mismatches.join("\n")
+                );
+            }
+        }
+        let compute_bind_group_layout =
+            render_device.create_bind_group_layout("snake compute bind group layout", &entries);
 
+        let sdf_shader = world
+            .resource::<AssetServer>()
+            .load("shaders/snake_sdf.wgsl");
         let shader = world
             .resource::<AssetServer>()
             .load("shaders/snake_compute.wgsl");
@@ -167,6 +266,26 @@ impl FromWorld for SnakeComputePipeline {
                 entry_point: Cow::from("connect_vertices"),
             });
 
+        let relax_vertices_pipeline =
+            pipeline_cache.queue_compute_pipeline(ComputePipelineDescriptor {
+                label: Some("snake relax_vertices pipeline".into()),
+                layout: vec![compute_bind_group_layout.clone()],
+                push_constant_ranges: Vec::new(),
+                shader: shader.clone(),
+                shader_defs: vec![],
+                entry_point: Cow::from("relax_vertices"),
+            });
+
+        let apply_relaxed_vertices_pipeline =
+            pipeline_cache.queue_compute_pipeline(ComputePipelineDescriptor {
+                label: Some("snake apply_relaxed_vertices pipeline".into()),
+                layout: vec![compute_bind_group_layout.clone()],
+                push_constant_ranges: Vec::new(),
+                shader: shader.clone(),
+                shader_defs: vec![],
+                entry_point: Cow::from("apply_relaxed_vertices"),
+            });
+
         let prepare_indirect_buffer_pipeline =
             pipeline_cache.queue_compute_pipeline(ComputePipelineDescriptor {
                 label: Some("snake prepare_indirect_buffer pipeline".into()),
@@ -179,8 +298,11 @@ impl FromWorld for SnakeComputePipeline {
 
         SnakeComputePipeline {
             compute_bind_group_layout,
+            sdf_shader,
             find_vertices_pipeline,
             connect_vertices_pipeline,
+            relax_vertices_pipeline,
+            apply_relaxed_vertices_pipeline,
             prepare_indirect_buffer_pipeline,
         }
     }
@@ -0,0 +1,165 @@
+use bevy::prelude::*;
+
+#[derive(Copy, Clone, Debug, PartialEq, Reflect)]
+pub struct RadiusKeyframe {
+    // 0.0 is the head, 1.0 is the tip of the tail
+    pub position: f32,
+    // multiplier of SnakeMesh::radius
+    pub scale: f32,
+}
+
+// Local bump on top of the keyframes, like prey being swallowed
+#[derive(Copy, Clone, Debug, PartialEq, Reflect)]
+pub struct RadiusBulge {
+    // same units as RadiusKeyframe::position
+    pub position: f32,
+    // distance from the center to where the bump fades out
+    pub width: f32,
+    // added to the radius scale at the center, relative to the keyframes
+    pub strength: f32,
+    // body lengths per second toward the tail
+    pub speed: f32,
+}
+
+impl RadiusBulge {
+    pub fn scale(&self, position: f32) -> f32 {
+        let d = ((position - self.position) / self.width.max(f32::EPSILON)).abs();
+        if d >= 1.0 {
+            return 0.0;
+        }
+        let falloff = 1.0 - d * d;
+        self.strength * falloff * falloff
+    }
+}
+
+// Thickness of the snake along its body, linearly interpolated between keyframes sorted by
+// position. Without keyframes the snake is equally thick everywhere.
+#[derive(Clone, Debug, Default, PartialEq, Reflect)]
+pub struct RadiusProfile {
+    pub keyframes: Vec<RadiusKeyframe>,
+    pub bulges: Vec<RadiusBulge>,
+    // 0.0 is alive, at 1.0 the whole body has shrunk away, starting from the tail
+    pub deflation: f32,
+}
+
+impl RadiusProfile {
+    pub fn new(keyframes: impl IntoIterator<Item = (f32, f32)>) -> Self {
+        let mut keyframes: Vec<RadiusKeyframe> = keyframes
+            .into_iter()
+            .map(|(position, scale)| RadiusKeyframe { position, scale })
+            .collect();
+        keyframes.sort_by(|a, b| a.position.total_cmp(&b.position));
+        Self {
+            keyframes,
+            bulges: Vec::new(),
+            deflation: 0.0,
+        }
+    }
+
+    // head bulge, neck pinch and a tail tapering to a point
+    pub fn snake() -> Self {
+        Self::new([
+            (0.0, 0.8),
+            (0.04, 1.3),
+            (0.1, 0.7),
+            (0.2, 1.0),
+            (0.6, 1.0),
+            (1.0, 0.0),
+        ])
+    }
+
+    // upper bound, overlapping bulges are assumed to stack
+    pub fn max_scale(&self) -> f32 {
+        let keyframes_max = self
+            .keyframes
+            .iter()
+            .map(|keyframe| keyframe.scale)
+            .reduce(f32::max)
+            .unwrap_or(1.0);
+        keyframes_max * (1.0 + self.bulges.iter().map(|bulge| bulge.strength).sum::<f32>())
+    }
+
+    pub fn sample(&self, position: f32) -> f32 {
+        let bulges: f32 = self.bulges.iter().map(|bulge| bulge.scale(position)).sum();
+        self.sample_keyframes(position) * (1.0 + bulges) * self.deflation_scale(position)
+    }
+
+    // the deflated part moves from the tail to the head and fades over DEFLATION_WIDTH
+    pub fn deflation_scale(&self, position: f32) -> f32 {
+        const DEFLATION_WIDTH: f32 = 0.2;
+        if self.deflation <= 0.0 {
+            return 1.0;
+        }
+        let front = 1.0 - self.deflation * (1.0 + DEFLATION_WIDTH);
+        ((front + DEFLATION_WIDTH - position) / DEFLATION_WIDTH).clamp(0.0, 1.0)
+    }
+
+    fn sample_keyframes(&self, position: f32) -> f32 {
+        let (Some(first), Some(last)) = (self.keyframes.first(), self.keyframes.last()) else {
+            return 1.0;
+        };
+        if position <= first.position {
+            return first.scale;
+        }
+        if position >= last.position {
+            return last.scale;
+        }
+        let next = self
+            .keyframes
+            .partition_point(|keyframe| keyframe.position <= position);
+        let (a, b) = (self.keyframes[next - 1], self.keyframes[next]);
+        let t = (position - a.position) / (b.position - a.position).max(f32::EPSILON);
+        a.scale + (b.scale - a.scale) * t
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{RadiusBulge, RadiusProfile};
+
+    #[test]
+    fn test_sample() {
+        let profile = RadiusProfile::new([(1.0, 0.0), (0.0, 1.0), (0.5, 2.0)]);
+        let test_cases = [
+            (-1.0, 1.0),
+            (0.0, 1.0),
+            (0.25, 1.5),
+            (0.5, 2.0),
+            (0.75, 1.0),
+            (1.0, 0.0),
+            (2.0, 0.0),
+        ];
+        for (position, expected) in test_cases {
+            assert!((profile.sample(position) - expected).abs() < 1e-6);
+        }
+        assert_eq!(RadiusProfile::default().sample(0.3), 1.0);
+    }
+
+    #[test]
+    fn test_bulges() {
+        let mut profile = RadiusProfile::new([(0.0, 2.0), (1.0, 2.0)]);
+        let bulge = RadiusBulge {
+            position: 0.5,
+            width: 0.1,
+            strength: 0.5,
+            speed: 1.0,
+        };
+        profile.bulges = vec![bulge, bulge];
+        assert!((profile.sample(0.5) - 4.0).abs() < 1e-6);
+        assert!((profile.sample(0.45) - 2.0 * (1.0 + 2.0 * 0.5 * 0.5625)).abs() < 1e-6);
+        assert_eq!(profile.sample(0.65), 2.0);
+        assert_eq!(profile.sample(0.2), 2.0);
+        assert_eq!(profile.max_scale(), 4.0);
+    }
+
+    #[test]
+    fn test_deflation() {
+        let mut profile = RadiusProfile::default();
+        profile.deflation = 0.5;
+        assert_eq!(profile.sample(0.0), 1.0);
+        assert_eq!(profile.sample(1.0), 0.0);
+        assert!(profile.sample(0.45) > profile.sample(0.55));
+        profile.deflation = 1.0;
+        assert_eq!(profile.sample(0.0), 0.0);
+    }
+}
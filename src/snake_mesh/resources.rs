@@ -4,34 +4,30 @@ use bevy::{
     prelude::*,
     render::render_resource::{BindGroup, Buffer, ShaderType},
 };
+
+use super::sdf::{SnakeSdf, SnakeSdfUniform};
+
 #[derive(Default, Resource, Deref, DerefMut)]
 pub struct SnakeMeshInstances(EntityHashMap<SnakeMeshInstance>);
 
-#[derive(ShaderType, Copy, Clone, Debug, PartialEq, Reflect, bytemuck::Pod, bytemuck::Zeroable)]
+#[derive(ShaderType, Copy, Clone, Debug, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
 #[repr(C)]
 pub struct SnakeMeshUniforms {
     pub grid_size: Vec3,
     _padding0: u32,
     pub grid_origin: Vec3,
     _padding1: u32,
-    pub sphere_origin: Vec3,
-    pub sphere_radius: f32,
+    pub snake: SnakeSdfUniform,
 }
 
 impl SnakeMeshUniforms {
-    pub fn new(
-        grid_size: Vec3,
-        grid_origin: Vec3,
-        sphere_origin: Vec3,
-        sphere_radius: f32,
-    ) -> Self {
+    pub fn new(grid_size: Vec3, grid_origin: Vec3, snake: &SnakeSdf) -> Self {
         Self {
             grid_size,
             _padding0: 0,
             grid_origin,
             _padding1: 0,
-            sphere_origin,
-            sphere_radius,
+            snake: snake.uniform(),
         }
     }
 }
@@ -39,10 +35,15 @@ impl SnakeMeshUniforms {
 pub struct SnakeMeshInstance {
     pub fake_mesh_asset: AssetId<Mesh>,
     pub uniforms: SnakeMeshUniforms,
+    pub previous_uniforms: SnakeMeshUniforms,
+    pub resolution: u32,
+    pub relaxation_iterations: u32,
     pub uniform_buffer: Option<Buffer>,
+    pub previous_uniform_buffer: Option<Buffer>,
     pub vertex_buffer: Option<Buffer>,
     pub index_buffer: Option<Buffer>,
     pub cell_buffer: Option<Buffer>,
+    pub relaxed_buffer: Option<Buffer>,
     pub atomics_buffer: Option<Buffer>,
     pub indirect_buffer: Option<Buffer>,
     pub compute_bind_group: Option<BindGroup>,
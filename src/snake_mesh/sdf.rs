@@ -0,0 +1,876 @@
+use bevy::{prelude::*, render::render_resource::ShaderType};
+
+use super::{colors::SnakeColors, profile::RadiusProfile};
+
+// matches snake_sdf.wgsl
+pub const MAX_BODY_POINTS: usize = 128;
+// radius scales are packed 4 per vector
+pub const RADIUS_SCALE_VECTORS: usize = MAX_BODY_POINTS / 4;
+pub const GRADIENT_STEP: f32 = 0.001;
+
+// Signed distance function, negative inside of the shape
+pub trait Sdf {
+    fn distance(&self, point: Vec3) -> f32;
+
+    // central differences, same as sdf_gradient() in snake_sdf.wgsl
+    fn gradient(&self, point: Vec3) -> Vec3 {
+        let dx = Vec3::new(GRADIENT_STEP, 0.0, 0.0);
+        let dy = Vec3::new(0.0, GRADIENT_STEP, 0.0);
+        let dz = Vec3::new(0.0, 0.0, GRADIENT_STEP);
+        Vec3::new(
+            self.distance(point + dx) - self.distance(point - dx),
+            self.distance(point + dy) - self.distance(point - dy),
+            self.distance(point + dz) - self.distance(point - dz),
+        ) / (2.0 * GRADIENT_STEP)
+    }
+
+    // linear rgba written into the vertex colors
+    fn color(&self, _point: Vec3) -> Vec4 {
+        Vec4::ONE
+    }
+
+    fn normal(&self, point: Vec3) -> Vec3 {
+        self.gradient(point).normalize_or_zero()
+    }
+
+    // exact for shapes with a unit length gradient, good enough for the rest
+    fn closest_point(&self, point: Vec3) -> Vec3 {
+        point - self.distance(point) * self.normal(point)
+    }
+
+    // sphere tracing, direction has to be normalized. Returns the distance along the ray
+    fn raycast(&self, origin: Vec3, direction: Vec3, max_distance: f32) -> Option<f32> {
+        const MAX_STEPS: u32 = 256;
+        const HIT_DISTANCE: f32 = 1e-4;
+        let mut traveled = 0.0;
+        for _ in 0..MAX_STEPS {
+            let distance = self.distance(origin + direction * traveled);
+            if distance < HIT_DISTANCE {
+                return Some(traveled);
+            }
+            traveled += distance;
+            if traveled > max_distance {
+                return None;
+            }
+        }
+        None
+    }
+}
+
+// Two eyeballs smoothly blended into the head, in the snake's local space
+#[derive(Copy, Clone, Debug, PartialEq, Reflect)]
+pub struct SnakeEyes {
+    pub left: Vec3,
+    pub right: Vec3,
+    pub radius: f32,
+    // 1.0 is open, 0.0 is closed
+    pub openness: f32,
+    // distance over which the eyes blend into the head
+    pub smoothness: f32,
+}
+
+impl SnakeEyes {
+    // closing eyes get squashed vertically
+    fn radii(&self) -> Vec3 {
+        let lid = 0.15 + 0.85 * self.openness.clamp(0.0, 1.0);
+        Vec3::new(self.radius, self.radius * lid, self.radius)
+    }
+
+    fn blend(&self, point: Vec3, distance: f32) -> f32 {
+        if self.radius <= 0.0 {
+            return distance;
+        }
+        let radii = self.radii();
+        [self.left, self.right]
+            .iter()
+            .fold(distance, |distance, eye| {
+                smooth_union(
+                    distance,
+                    ellipsoid_distance(point - *eye, radii),
+                    self.smoothness,
+                )
+            })
+    }
+
+    pub fn uniform(&self) -> SnakeEyesUniform {
+        SnakeEyesUniform {
+            left: self.left.extend(self.radius),
+            right: self.right.extend(self.radius),
+            openness: self.openness,
+            smoothness: self.smoothness,
+            _padding0: 0,
+            _padding1: 0,
+        }
+    }
+}
+
+// Eats holes into the surface where value noise is below the threshold
+#[derive(Copy, Clone, Debug, PartialEq, Reflect)]
+pub struct SnakeDissolve {
+    // 0.0 keeps everything, 1.0 removes everything
+    pub threshold: f32,
+    // noise cells per unit of length
+    pub frequency: f32,
+}
+
+impl SnakeDissolve {
+    fn apply(&self, point: Vec3, distance: f32) -> f32 {
+        if self.frequency <= 0.0 {
+            return distance;
+        }
+        distance.max((self.threshold - value_noise(point * self.frequency)) / self.frequency)
+    }
+
+    // x is the threshold, y the frequency
+    pub fn uniform(&self) -> Vec4 {
+        Vec4::new(self.threshold, self.frequency, 0.0, 0.0)
+    }
+}
+
+// pcg hash, integer math so the gpu gets exactly the same values
+fn hash(x: u32) -> u32 {
+    let state = x.wrapping_mul(747796405).wrapping_add(2891336453);
+    let word = ((state >> ((state >> 28) + 4)) ^ state).wrapping_mul(277803737);
+    (word >> 22) ^ word
+}
+
+fn corner_value(cell: IVec3) -> f32 {
+    let hash = hash(cell.x as u32 ^ hash(cell.y as u32 ^ hash(cell.z as u32)));
+    hash as f32 / u32::MAX as f32
+}
+
+// smoothed value noise in [0, 1], same as value_noise() in snake_sdf.wgsl
+fn value_noise(point: Vec3) -> f32 {
+    let floor = point.floor();
+    let f = point - floor;
+    let u = f * f * (3.0 - 2.0 * f);
+    let cell = floor.as_ivec3();
+    let corner = |x: i32, y: i32, z: i32| corner_value(cell + IVec3::new(x, y, z));
+    let lerp = |a: f32, b: f32, t: f32| a + (b - a) * t;
+    let y0 = lerp(
+        lerp(corner(0, 0, 0), corner(1, 0, 0), u.x),
+        lerp(corner(0, 1, 0), corner(1, 1, 0), u.x),
+        u.y,
+    );
+    let y1 = lerp(
+        lerp(corner(0, 0, 1), corner(1, 0, 1), u.x),
+        lerp(corner(0, 1, 1), corner(1, 1, 1), u.x),
+        u.y,
+    );
+    lerp(y0, y1, u.z)
+}
+
+// Chain of round cones going through the body points, the radius at every point is scaled by
+// a RadiusProfile, everything is in the snake's local space.
+// A single point body is a sphere.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SnakeSdf {
+    pub radius: f32,
+    pub body: Vec<Vec3>,
+    // multiplier of the radius at every body point
+    pub radius_scales: Vec<f32>,
+    // linear rgba at every body point
+    pub colors: Vec<Vec4>,
+    pub eyes: Option<SnakeEyes>,
+    pub dissolve: Option<SnakeDissolve>,
+}
+
+impl SnakeSdf {
+    pub fn new(radius: f32, body: Vec<Vec3>) -> Self {
+        Self::with_profile(radius, body, &RadiusProfile::default())
+    }
+
+    pub fn with_profile(radius: f32, mut body: Vec<Vec3>, profile: &RadiusProfile) -> Self {
+        if body.len() > MAX_BODY_POINTS {
+            warn!(
+                "snake body has {} points, only {} are supported",
+                body.len(),
+                MAX_BODY_POINTS
+            );
+            body.truncate(MAX_BODY_POINTS);
+        }
+        let radius_scales = body_positions(&body)
+            .into_iter()
+            .map(|position| profile.sample(position))
+            .collect();
+        Self {
+            radius,
+            colors: vec![Vec4::ONE; body.len()],
+            body,
+            radius_scales,
+            eyes: None,
+            dissolve: None,
+        }
+    }
+
+    pub fn with_colors(mut self, colors: &SnakeColors) -> Self {
+        self.colors = body_positions(&self.body)
+            .into_iter()
+            .enumerate()
+            .map(|(segment, position)| colors.sample(segment, position))
+            .collect();
+        self
+    }
+
+    pub fn with_eyes(mut self, eyes: Option<SnakeEyes>) -> Self {
+        self.eyes = eyes;
+        self
+    }
+
+    pub fn with_dissolve(mut self, dissolve: Option<SnakeDissolve>) -> Self {
+        self.dissolve = dissolve;
+        self
+    }
+
+    // distance along the body from the first point to the body point closest to `point`
+    pub fn arc_length(&self, point: Vec3) -> f32 {
+        let mut closest_distance = f32::MAX;
+        let mut arc_length = 0.0;
+        let mut segment_start = 0.0;
+        for segment in self.body.windows(2) {
+            let ba = segment[1] - segment[0];
+            let segment_length = ba.length();
+            let h = ((point - segment[0]).dot(ba) / ba.dot(ba).max(1e-8)).clamp(0.0, 1.0);
+            let distance = (point - segment[0] - ba * h).length();
+            if distance < closest_distance {
+                closest_distance = distance;
+                arc_length = segment_start + h * segment_length;
+            }
+            segment_start += segment_length;
+        }
+        arc_length
+    }
+
+    // where `point` was on the `previous` body: same arc length from the first point and the
+    // same offset from the centerline, turned with the segment. None for bodies of one point
+    pub fn follow_body(&self, previous: &SnakeSdf, point: Vec3) -> Option<Vec3> {
+        if self.body.len() < 2 || previous.body.len() < 2 {
+            return None;
+        }
+        let mut closest_distance = f32::MAX;
+        let (mut arc_length, mut offset, mut tangent) = (0.0, Vec3::ZERO, Vec3::ZERO);
+        let mut segment_start = 0.0;
+        for segment in self.body.windows(2) {
+            let ba = segment[1] - segment[0];
+            let segment_length = ba.length();
+            let h = ((point - segment[0]).dot(ba) / ba.dot(ba).max(1e-8)).clamp(0.0, 1.0);
+            let center = segment[0] + ba * h;
+            let distance = (point - center).length();
+            if distance < closest_distance {
+                closest_distance = distance;
+                arc_length = segment_start + h * segment_length;
+                offset = point - center;
+                tangent = ba.normalize_or_zero();
+            }
+            segment_start += segment_length;
+        }
+        // the last segment is extended when the previous body was shorter
+        let mut segment_start = 0.0;
+        for (i, segment) in previous.body.windows(2).enumerate() {
+            let ba = segment[1] - segment[0];
+            let segment_length = ba.length();
+            if arc_length <= segment_start + segment_length || i + 2 == previous.body.len() {
+                let h = (arc_length - segment_start) / segment_length.max(1e-8);
+                let previous_tangent = ba.normalize_or_zero();
+                return Some(
+                    segment[0] + ba * h + rotate_between(offset, tangent, previous_tangent),
+                );
+            }
+            segment_start += segment_length;
+        }
+        None
+    }
+
+    pub fn uniform(&self) -> SnakeSdfUniform {
+        let mut points = [Vec4::ZERO; MAX_BODY_POINTS];
+        let mut radius_scales = [Vec4::ZERO; RADIUS_SCALE_VECTORS];
+        let mut colors = [Vec4::ONE; MAX_BODY_POINTS];
+        let mut arc_length = 0.0;
+        for (i, point) in self.body.iter().enumerate() {
+            if i > 0 {
+                arc_length += point.distance(self.body[i - 1]);
+            }
+            points[i] = point.extend(arc_length);
+            radius_scales[i / 4][i % 4] = self.radius_scales[i];
+            colors[i] = self.colors[i];
+        }
+        SnakeSdfUniform {
+            radius: self.radius,
+            point_count: self.body.len() as u32,
+            _padding0: 0,
+            _padding1: 0,
+            points,
+            radius_scales,
+            colors,
+            eyes: self
+                .eyes
+                .map(|eyes| eyes.uniform())
+                .unwrap_or_else(bytemuck::Zeroable::zeroed),
+            dissolve: self
+                .dissolve
+                .map_or(Vec4::ZERO, |dissolve| dissolve.uniform()),
+        }
+    }
+
+    fn point_radius(&self, index: usize) -> f32 {
+        self.radius * self.radius_scales[index]
+    }
+}
+
+impl Sdf for SnakeSdf {
+    fn distance(&self, point: Vec3) -> f32 {
+        let body_distance = match self.body.as_slice() {
+            [] => 1e10,
+            [center] => point.distance(*center) - self.point_radius(0),
+            body => body
+                .windows(2)
+                .enumerate()
+                .map(|(i, segment)| {
+                    round_cone_distance(
+                        point,
+                        segment[0],
+                        segment[1],
+                        self.point_radius(i),
+                        self.point_radius(i + 1),
+                    )
+                })
+                .fold(1e10, f32::min),
+        };
+        let distance = match self.eyes {
+            Some(eyes) => eyes.blend(point, body_distance),
+            None => body_distance,
+        };
+        match self.dissolve {
+            Some(dissolve) => dissolve.apply(point, distance),
+            None => distance,
+        }
+    }
+
+    // interpolated along the segment whose surface is closest
+    fn color(&self, point: Vec3) -> Vec4 {
+        match self.body.as_slice() {
+            [] => Vec4::ONE,
+            [_] => self.colors[0],
+            body => {
+                let mut closest_distance = f32::MAX;
+                let mut color = self.colors[0];
+                for (i, segment) in body.windows(2).enumerate() {
+                    let distance = round_cone_distance(
+                        point,
+                        segment[0],
+                        segment[1],
+                        self.point_radius(i),
+                        self.point_radius(i + 1),
+                    );
+                    if distance < closest_distance {
+                        closest_distance = distance;
+                        let ba = segment[1] - segment[0];
+                        let h =
+                            ((point - segment[0]).dot(ba) / ba.dot(ba).max(1e-8)).clamp(0.0, 1.0);
+                        color = self.colors[i].lerp(self.colors[i + 1], h);
+                    }
+                }
+                color
+            }
+        }
+    }
+}
+
+// arc length of every body point divided by the length of the whole body
+fn body_positions(body: &[Vec3]) -> Vec<f32> {
+    let mut arc_lengths = Vec::with_capacity(body.len());
+    let mut arc_length = 0.0;
+    for (i, point) in body.iter().enumerate() {
+        if i > 0 {
+            arc_length += point.distance(body[i - 1]);
+        }
+        arc_lengths.push(arc_length);
+    }
+    let total_length = arc_length.max(f32::EPSILON);
+    arc_lengths
+        .into_iter()
+        .map(|arc_length| arc_length / total_length)
+        .collect()
+}
+
+// polynomial smooth min, https://iquilezles.org/articles/smin/
+fn smooth_union(a: f32, b: f32, k: f32) -> f32 {
+    let k = k.max(1e-4);
+    let h = (0.5 + 0.5 * (b - a) / k).clamp(0.0, 1.0);
+    b + (a - b) * h - k * h * (1.0 - h)
+}
+
+// not exact, but a good enough bound for sphere tracing
+fn ellipsoid_distance(point: Vec3, radii: Vec3) -> f32 {
+    let k0 = (point / radii).length();
+    let k1 = (point / (radii * radii)).length();
+    if k1 == 0.0 {
+        return -radii.min_element();
+    }
+    k0 * (k0 - 1.0) / k1
+}
+
+// same as sign() in wgsl, 0.0 stays 0.0
+fn sign(x: f32) -> f32 {
+    if x == 0.0 {
+        0.0
+    } else {
+        x.signum()
+    }
+}
+
+// exact distance to the convex hull of two spheres, https://iquilezles.org/articles/distfunctions/
+fn round_cone_distance(point: Vec3, a: Vec3, b: Vec3, radius_a: f32, radius_b: f32) -> f32 {
+    let ba = b - a;
+    let l2 = ba.dot(ba);
+    let rr = radius_a - radius_b;
+    let a2 = l2 - rr * rr;
+    // one sphere is inside of the other one
+    if a2 <= 1e-8 {
+        return (point.distance(a) - radius_a).min(point.distance(b) - radius_b);
+    }
+    let il2 = 1.0 / l2;
+    let pa = point - a;
+    let y = pa.dot(ba);
+    let z = y - l2;
+    let x2 = (pa * l2 - ba * y).length_squared();
+    let y2 = y * y * l2;
+    let z2 = z * z * l2;
+    let k = sign(rr) * rr * rr * x2;
+    if sign(z) * a2 * z2 > k {
+        return (x2 + z2).sqrt() * il2 - radius_b;
+    }
+    if sign(y) * a2 * y2 < k {
+        return (x2 + y2).sqrt() * il2 - radius_a;
+    }
+    ((x2 * a2 * il2).sqrt() + y * rr) * il2 - radius_a
+}
+
+// shortest rotation taking the unit vector `from` to `to`, rodrigues' formula
+fn rotate_between(v: Vec3, from: Vec3, to: Vec3) -> Vec3 {
+    let c = from.dot(to);
+    // degenerate or reversed segments have no single rotation to pick
+    if from == Vec3::ZERO || to == Vec3::ZERO || c < -0.9999 {
+        return v;
+    }
+    let k = from.cross(to);
+    v * c + k.cross(v) + k * k.dot(v) / (1.0 + c)
+}
+
+#[derive(ShaderType, Copy, Clone, Debug, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+#[repr(C)]
+pub struct SnakeSdfUniform {
+    pub radius: f32,
+    pub point_count: u32,
+    _padding0: u32,
+    _padding1: u32,
+    pub points: [Vec4; MAX_BODY_POINTS],
+    pub radius_scales: [Vec4; RADIUS_SCALE_VECTORS],
+    pub colors: [Vec4; MAX_BODY_POINTS],
+    pub eyes: SnakeEyesUniform,
+    // SnakeDissolve::uniform(), zero when the snake isn't dissolving
+    pub dissolve: Vec4,
+}
+
+#[derive(ShaderType, Copy, Clone, Debug, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+#[repr(C)]
+pub struct SnakeEyesUniform {
+    // xyz is the center, w is the radius. Zero radius means no eyes
+    pub left: Vec4,
+    pub right: Vec4,
+    pub openness: f32,
+    pub smoothness: f32,
+    _padding0: u32,
+    _padding1: u32,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Sdf, SnakeDissolve, SnakeEyes, SnakeSdf};
+    use crate::snake_mesh::{RadiusProfile, SnakeColors};
+    use bevy::prelude::*;
+
+    fn test_snakes() -> Vec<SnakeSdf> {
+        vec![
+            SnakeSdf::new(5.0, vec![Vec3::ZERO]),
+            SnakeSdf::new(1.5, vec![Vec3::new(1.0, 2.0, 3.0)]),
+            SnakeSdf::new(
+                1.0,
+                vec![
+                    Vec3::new(-6.0, 0.0, 0.0),
+                    Vec3::new(-2.0, 0.0, 1.0),
+                    Vec3::new(2.0, 0.0, -1.0),
+                    Vec3::new(6.0, 1.0, 0.0),
+                ],
+            ),
+        ]
+    }
+
+    fn tapered_snake() -> SnakeSdf {
+        SnakeSdf::with_profile(
+            1.0,
+            vec![
+                Vec3::new(-6.0, 0.0, 0.0),
+                Vec3::new(-4.0, 0.5, 0.0),
+                Vec3::new(-2.0, 0.0, 1.0),
+                Vec3::new(2.0, 0.0, -1.0),
+                Vec3::new(6.0, 1.0, 0.0),
+            ],
+            &RadiusProfile::snake(),
+        )
+        .with_eyes(Some(SnakeEyes {
+            left: Vec3::new(-5.8, 0.6, 0.7),
+            right: Vec3::new(-5.8, 0.6, -0.7),
+            radius: 0.3,
+            openness: 0.6,
+            smoothness: 0.1,
+        }))
+        .with_dissolve(Some(SnakeDissolve {
+            threshold: 0.3,
+            frequency: 2.0,
+        }))
+    }
+
+    fn test_points() -> Vec<Vec3> {
+        let mut points = Vec::new();
+        for x in -4..=4 {
+            for y in -4..=4 {
+                for z in -4..=4 {
+                    points.push(Vec3::new(x as f32, y as f32, z as f32) * 2.1 + 0.3);
+                }
+            }
+        }
+        points
+    }
+
+    #[test]
+    fn test_sphere_distance() {
+        let sphere = SnakeSdf::new(2.0, vec![Vec3::new(1.0, 0.0, 0.0)]);
+        assert!((sphere.distance(Vec3::new(1.0, 0.0, 0.0)) + 2.0).abs() < 1e-6);
+        assert!((sphere.distance(Vec3::new(4.0, 0.0, 0.0)) - 1.0).abs() < 1e-6);
+        assert!(sphere
+            .normal(Vec3::new(1.0, 5.0, 0.0))
+            .abs_diff_eq(Vec3::Y, 1e-3));
+    }
+
+    #[test]
+    fn test_capsule_chain_distance() {
+        let snake = SnakeSdf::new(
+            1.0,
+            vec![
+                Vec3::ZERO,
+                Vec3::new(4.0, 0.0, 0.0),
+                Vec3::new(4.0, 0.0, 4.0),
+            ],
+        );
+        assert!((snake.distance(Vec3::new(2.0, 3.0, 0.0)) - 2.0).abs() < 1e-6);
+        assert!((snake.distance(Vec3::new(4.0, 0.0, 2.0)) + 1.0).abs() < 1e-6);
+        assert!((snake.distance(Vec3::new(-3.0, 0.0, 0.0)) - 2.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_closest_point_is_on_surface() {
+        for snake in test_snakes() {
+            for point in test_points() {
+                let closest = snake.closest_point(point);
+                assert!(
+                    snake.distance(closest).abs() < 1e-3,
+                    "{:?} is not on the surface",
+                    closest
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_radius_profile() {
+        let profile = RadiusProfile::new([(0.0, 2.0), (1.0, 0.0)]);
+        let snake = SnakeSdf::with_profile(
+            1.0,
+            vec![
+                Vec3::ZERO,
+                Vec3::new(5.0, 0.0, 0.0),
+                Vec3::new(10.0, 0.0, 0.0),
+            ],
+            &profile,
+        );
+        assert_eq!(snake.radius_scales, vec![2.0, 1.0, 0.0]);
+        assert!((snake.distance(Vec3::new(0.0, 3.0, 0.0)) - 1.0).abs() < 1e-5);
+        // on the side of the cone, not above the sphere
+        assert!((snake.distance(Vec3::new(5.0, 3.0, 0.0)) - 1.9394).abs() < 1e-3);
+        assert!(snake.distance(Vec3::new(10.0, 0.01, 0.0)) > 0.0);
+        assert_eq!(
+            snake.uniform().radius_scales[0].truncate(),
+            Vec3::new(2.0, 1.0, 0.0)
+        );
+    }
+
+    #[test]
+    fn test_dissolve() {
+        let snake = SnakeSdf::new(5.0, vec![Vec3::ZERO]);
+        let dissolve = |threshold| {
+            snake.clone().with_dissolve(Some(SnakeDissolve {
+                threshold,
+                frequency: 1.5,
+            }))
+        };
+        for point in test_points() {
+            // only the inside changes, the surface stays where it was
+            let distance = snake.distance(point);
+            if distance >= 0.0 {
+                assert_eq!(dissolve(0.0).distance(point), distance);
+            }
+            assert!(dissolve(1.0).distance(point) >= 0.0);
+        }
+    }
+
+    #[test]
+    fn test_colors() {
+        let snake = SnakeSdf::new(
+            1.0,
+            vec![
+                Vec3::ZERO,
+                Vec3::new(4.0, 0.0, 0.0),
+                Vec3::new(4.0, 0.0, 4.0),
+            ],
+        )
+        .with_colors(&SnakeColors::gradient(Color::WHITE, Color::BLACK));
+        assert_eq!(snake.colors[1], Vec4::new(0.5, 0.5, 0.5, 1.0));
+        assert_eq!(snake.color(Vec3::new(-2.0, 1.0, 0.0)), Vec4::ONE);
+        assert_eq!(
+            snake.color(Vec3::new(2.0, 1.0, 0.0)),
+            Vec4::new(0.75, 0.75, 0.75, 1.0)
+        );
+        assert_eq!(
+            snake.color(Vec3::new(5.0, 0.0, 4.0)),
+            Vec4::new(0.0, 0.0, 0.0, 1.0)
+        );
+        assert_eq!(SnakeSdf::new(1.0, vec![]).color(Vec3::ZERO), Vec4::ONE);
+    }
+
+    #[test]
+    fn test_uniform_arc_length() {
+        let snake = SnakeSdf::new(
+            1.0,
+            vec![
+                Vec3::ZERO,
+                Vec3::new(3.0, 0.0, 0.0),
+                Vec3::new(3.0, 4.0, 0.0),
+            ],
+        );
+        let uniform = snake.uniform();
+        assert_eq!(uniform.point_count, 3);
+        assert_eq!(uniform.points[0].w, 0.0);
+        assert_eq!(uniform.points[1].w, 3.0);
+        assert_eq!(uniform.points[2].w, 7.0);
+    }
+
+    #[test]
+    fn test_arc_length() {
+        let snake = SnakeSdf::new(
+            1.0,
+            vec![
+                Vec3::ZERO,
+                Vec3::new(3.0, 0.0, 0.0),
+                Vec3::new(3.0, 4.0, 0.0),
+            ],
+        );
+        assert_eq!(snake.arc_length(Vec3::new(-1.0, 0.0, 0.0)), 0.0);
+        assert!((snake.arc_length(Vec3::new(1.5, 1.0, 0.0)) - 1.5).abs() < 1e-6);
+        assert!((snake.arc_length(Vec3::new(4.0, 2.0, 0.0)) - 5.0).abs() < 1e-6);
+        assert!((snake.arc_length(Vec3::new(3.0, 9.0, 0.0)) - 7.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_follow_body() {
+        let body = vec![Vec3::ZERO, Vec3::new(4.0, 0.0, 0.0)];
+        let snake = SnakeSdf::new(1.0, body.clone());
+        // sliding along the body is tangential, the closest point of the surface wouldn't move
+        let slid = SnakeSdf::new(1.0, body.iter().map(|p| *p - Vec3::X).collect());
+        let previous = snake.follow_body(&slid, Vec3::new(2.0, 1.0, 0.0)).unwrap();
+        assert!(previous.distance(Vec3::new(1.0, 1.0, 0.0)) < 1e-6);
+
+        let turned = SnakeSdf::new(1.0, vec![Vec3::ZERO, Vec3::new(0.0, 4.0, 0.0)]);
+        let previous = snake
+            .follow_body(&turned, Vec3::new(2.0, 1.0, 0.0))
+            .unwrap();
+        assert!(previous.distance(Vec3::new(-1.0, 2.0, 0.0)) < 1e-6);
+
+        let short = SnakeSdf::new(1.0, vec![Vec3::ZERO, Vec3::new(2.0, 0.0, 0.0)]);
+        let previous = snake.follow_body(&short, Vec3::new(3.0, 0.0, 1.0)).unwrap();
+        assert!(previous.distance(Vec3::new(3.0, 0.0, 1.0)) < 1e-6);
+
+        let sphere = SnakeSdf::new(1.0, vec![Vec3::ZERO]);
+        assert_eq!(snake.follow_body(&sphere, Vec3::X), None);
+    }
+
+    #[test]
+    fn test_raycast() {
+        let sphere = SnakeSdf::new(2.0, vec![Vec3::ZERO]);
+        let hit = sphere.raycast(Vec3::new(-10.0, 0.0, 0.0), Vec3::X, 100.0);
+        assert!((hit.unwrap() - 8.0).abs() < 1e-3);
+        assert_eq!(
+            sphere.raycast(Vec3::new(-10.0, 3.0, 0.0), Vec3::X, 100.0),
+            None
+        );
+        assert_eq!(
+            sphere.raycast(Vec3::new(-10.0, 0.0, 0.0), Vec3::X, 5.0),
+            None
+        );
+        assert_eq!(sphere.raycast(Vec3::ZERO, Vec3::X, 5.0), Some(0.0));
+
+        let snake = SnakeSdf::new(
+            1.0,
+            vec![
+                Vec3::ZERO,
+                Vec3::new(4.0, 0.0, 0.0),
+                Vec3::new(4.0, 0.0, 4.0),
+            ],
+        );
+        let hit = snake.raycast(Vec3::new(4.0, 10.0, 2.0), Vec3::NEG_Y, 100.0);
+        assert!((hit.unwrap() - 9.0).abs() < 1e-3);
+    }
+
+    // snake_sdf.wgsl with a compute entry point writing the gradient and distance of every point
+    fn sync_test_shader() -> String {
+        let sdf_module: String = include_str!("../../assets/shaders/snake_sdf.wgsl")
+            .lines()
+            .filter(|line| !line.starts_with("#define_import_path"))
+            .collect::<Vec<_>>()
+            .join("\n");
+        format!(
+            "{sdf_module}
+            @group(0) @binding(0) var<uniform> snake: SnakeSdf;
+            @group(0) @binding(1) var<storage, read> points: array<vec4<f32>>;
+            @group(0) @binding(2) var<storage, read_write> results: array<vec4<f32>>;
+
+            @compute @workgroup_size(64)
+            fn main(@builtin(global_invocation_id) id: vec3<u32>) {{
+                if id.x >= arrayLength(&points) {{
+                    return;
+                }}
+                let x = points[id.x].xyz;
+                results[id.x] = vec4<f32>(sdf_gradient(snake, x), sdf(snake, x));
+            }}"
+        )
+    }
+
+    // Runs without a gpu, so the shader side of test_gpu_matches_cpu can't silently rot
+    #[test]
+    fn test_sync_shader_is_valid() {
+        let source = sync_test_shader();
+        let module = naga::front::wgsl::parse_str(&source)
+            .unwrap_or_else(|error| panic!("{}", error.emit_to_string(&source)));
+        naga::valid::Validator::new(
+            naga::valid::ValidationFlags::all(),
+            naga::valid::Capabilities::all(),
+        )
+        .validate(&module)
+        .unwrap_or_else(|error| panic!("{}", error.emit_to_string(&source)));
+    }
+
+    // Evaluates snake_sdf.wgsl on the GPU and compares it with the rust implementation.
+    // Needs an adapter, run it with `cargo test -- --ignored`.
+    #[test]
+    #[ignore]
+    fn test_gpu_matches_cpu() {
+        use bevy::tasks::block_on;
+        use wgpu::util::DeviceExt;
+
+        let instance = wgpu::Instance::default();
+        let adapter = block_on(instance.request_adapter(&wgpu::RequestAdapterOptions::default()))
+            .expect("no gpu adapter");
+        let (device, queue) =
+            block_on(adapter.request_device(&wgpu::DeviceDescriptor::default(), None)).unwrap();
+
+        let source = sync_test_shader();
+        let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("sdf sync test"),
+            source: wgpu::ShaderSource::Wgsl(source.into()),
+        });
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: None,
+            layout: None,
+            module: &module,
+            entry_point: "main",
+        });
+
+        let points = test_points();
+        let gpu_points: Vec<Vec4> = points.iter().map(|point| point.extend(0.0)).collect();
+        let results_size = (gpu_points.len() * std::mem::size_of::<Vec4>()) as u64;
+
+        for snake in test_snakes().into_iter().chain([tapered_snake()]) {
+            let uniform_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: None,
+                contents: bytemuck::bytes_of(&snake.uniform()),
+                usage: wgpu::BufferUsages::UNIFORM,
+            });
+            let points_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: None,
+                contents: bytemuck::cast_slice(&gpu_points),
+                usage: wgpu::BufferUsages::STORAGE,
+            });
+            let results_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+                label: None,
+                size: results_size,
+                usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+                mapped_at_creation: false,
+            });
+            let readback_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+                label: None,
+                size: results_size,
+                usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            });
+            let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: None,
+                layout: &pipeline.get_bind_group_layout(0),
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: uniform_buffer.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: points_buffer.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 2,
+                        resource: results_buffer.as_entire_binding(),
+                    },
+                ],
+            });
+
+            let mut encoder = device.create_command_encoder(&Default::default());
+            {
+                let mut pass = encoder.begin_compute_pass(&Default::default());
+                pass.set_pipeline(&pipeline);
+                pass.set_bind_group(0, &bind_group, &[]);
+                pass.dispatch_workgroups((gpu_points.len() as u32).div_ceil(64), 1, 1);
+            }
+            encoder.copy_buffer_to_buffer(&results_buffer, 0, &readback_buffer, 0, results_size);
+            queue.submit(Some(encoder.finish()));
+
+            let slice = readback_buffer.slice(..);
+            slice.map_async(wgpu::MapMode::Read, |_| {});
+            device.poll(wgpu::Maintain::Wait);
+            let data = slice.get_mapped_range();
+            let results: &[Vec4] = bytemuck::cast_slice(&data);
+
+            for (point, result) in points.iter().zip(results) {
+                let distance = snake.distance(*point);
+                let gradient = snake.gradient(*point);
+                assert!(
+                    (distance - result.w).abs() < 1e-4,
+                    "distance mismatch at {point:?}: cpu {distance}, gpu {}",
+                    result.w
+                );
+                assert!(
+                    gradient.abs_diff_eq(result.xyz(), 1e-2),
+                    "gradient mismatch at {point:?}: cpu {gradient:?}, gpu {:?}",
+                    result.xyz()
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,183 @@
+use bevy::{
+    pbr::wireframe::{NoWireframe, Wireframe, WireframeColor, WireframeConfig, WireframeMaterial},
+    prelude::*,
+    render::primitives::Aabb,
+};
+
+use super::{
+    components::{
+        DeathStyle, PolygonizationSettings, PreyBulgeSettings, SnakeDead, SnakeDeath,
+        SnakeDebugView, SnakeMesh,
+    },
+    debug_material::SnakeDebugMaterial,
+    events::{PreySwallowed, SnakeDied},
+    profile::RadiusBulge,
+    sdf::SnakeDissolve,
+};
+
+pub fn spawn_prey_bulges(
+    mut events: EventReader<PreySwallowed>,
+    mut snakes: Query<(&mut SnakeMesh, Option<&PreyBulgeSettings>)>,
+) {
+    for event in events.read() {
+        let Ok((mut snake, settings)) = snakes.get_mut(event.snake) else {
+            continue;
+        };
+        let settings = settings.copied().unwrap_or_default();
+        snake.profile.bulges.push(RadiusBulge {
+            position: 0.0,
+            width: settings.width,
+            strength: settings.strength * event.size,
+            speed: 1.0 / settings.duration.max(f32::EPSILON),
+        });
+    }
+}
+
+pub fn move_prey_bulges(time: Res<Time>, mut snakes: Query<&mut SnakeMesh>) {
+    for mut snake in snakes.iter_mut() {
+        // keeps change detection quiet for snakes without bulges
+        if snake.profile.bulges.is_empty() {
+            continue;
+        }
+        for bulge in snake.profile.bulges.iter_mut() {
+            bulge.position += bulge.speed * time.delta_seconds();
+        }
+        snake
+            .profile
+            .bulges
+            .retain(|bulge| bulge.position - bulge.width < 1.0);
+    }
+}
+
+pub fn animate_deaths(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut snakes: Query<(Entity, &mut SnakeDeath, &mut SnakeMesh)>,
+    mut events: EventWriter<SnakeDied>,
+) {
+    for (entity, mut death, mut snake) in snakes.iter_mut() {
+        death.elapsed += time.delta_seconds();
+        let progress = death.progress();
+        match death.style {
+            DeathStyle::Deflate => snake.profile.deflation = progress,
+            DeathStyle::Dissolve { frequency } => {
+                snake.dissolve = Some(SnakeDissolve {
+                    threshold: progress,
+                    frequency,
+                })
+            }
+        }
+        if progress >= 1.0 {
+            // the snake stays invisible, despawning it is up to the game
+            commands
+                .entity(entity)
+                .remove::<SnakeDeath>()
+                .insert(SnakeDead);
+            events.send(SnakeDied { snake: entity });
+        }
+    }
+}
+
+// the surface never leaves the polygonization grid, so it's a valid bounding box for frustum culling
+pub fn update_snake_aabb(
+    mut commands: Commands,
+    snakes: Query<
+        (Entity, &PolygonizationSettings),
+        (With<SnakeMesh>, Changed<PolygonizationSettings>),
+    >,
+) {
+    for (entity, settings) in snakes.iter() {
+        commands.entity(entity).insert(Aabb::from_min_max(
+            settings.grid_origin,
+            settings.grid_origin + settings.grid_size,
+        ));
+    }
+}
+
+// bevy only applies wireframe materials to entities with a Handle<Mesh>
+pub fn apply_snake_wireframe_material(
+    mut commands: Commands,
+    mut materials: ResMut<Assets<WireframeMaterial>>,
+    config: Res<WireframeConfig>,
+    snakes: Query<
+        (
+            Entity,
+            Has<Wireframe>,
+            Has<NoWireframe>,
+            Option<&WireframeColor>,
+            Option<&Handle<WireframeMaterial>>,
+        ),
+        With<SnakeMesh>,
+    >,
+) {
+    for (entity, wireframe, no_wireframe, wireframe_color, material_handle) in snakes.iter() {
+        let enabled = !no_wireframe && (wireframe || config.global);
+        let color = wireframe_color
+            .map(|wireframe_color| wireframe_color.color)
+            .unwrap_or(config.default_color);
+        match (enabled, material_handle) {
+            (true, None) => {
+                commands
+                    .entity(entity)
+                    .insert(materials.add(WireframeMaterial { color }));
+            }
+            (true, Some(material_handle)) => {
+                let outdated = materials
+                    .get(material_handle)
+                    .is_some_and(|material| material.color != color);
+                if outdated {
+                    if let Some(material) = materials.get_mut(material_handle) {
+                        material.color = color;
+                    }
+                }
+            }
+            (false, Some(_)) => {
+                commands
+                    .entity(entity)
+                    .remove::<Handle<WireframeMaterial>>();
+            }
+            (false, None) => {}
+        }
+    }
+}
+
+pub fn apply_snake_debug_view(
+    mut commands: Commands,
+    mut materials: ResMut<Assets<SnakeDebugMaterial>>,
+    snakes: Query<
+        (
+            Entity,
+            &SnakeDebugView,
+            &PolygonizationSettings,
+            Option<&Handle<SnakeDebugMaterial>>,
+        ),
+        (
+            With<SnakeMesh>,
+            Or<(Changed<SnakeDebugView>, Changed<PolygonizationSettings>)>,
+        ),
+    >,
+    mut removed: RemovedComponents<SnakeDebugView>,
+) {
+    for (entity, debug_view, settings, material_handle) in snakes.iter() {
+        if *debug_view == SnakeDebugView::Disabled {
+            if material_handle.is_some() {
+                commands
+                    .entity(entity)
+                    .remove::<Handle<SnakeDebugMaterial>>();
+            }
+            continue;
+        }
+        let material = SnakeDebugMaterial::new(settings, *debug_view);
+        match material_handle.and_then(|handle| materials.get_mut(handle)) {
+            Some(existing) => *existing = material,
+            None => {
+                commands.entity(entity).insert(materials.add(material));
+            }
+        }
+    }
+    for entity in removed.read() {
+        if let Some(mut entity_commands) = commands.get_entity(entity) {
+            entity_commands.remove::<Handle<SnakeDebugMaterial>>();
+        }
+    }
+}
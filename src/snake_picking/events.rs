@@ -0,0 +1,15 @@
+use bevy::prelude::*;
+
+use super::SnakeRayHit;
+
+#[derive(Event, Clone, Copy, Debug)]
+pub struct SnakeHovered(pub SnakeRayHit);
+
+#[derive(Event, Clone, Copy, Debug)]
+pub struct SnakeUnhovered(pub Entity);
+
+#[derive(Event, Clone, Copy, Debug)]
+pub struct SnakeClicked {
+    pub hit: SnakeRayHit,
+    pub button: MouseButton,
+}
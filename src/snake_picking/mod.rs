@@ -0,0 +1,26 @@
+mod events;
+mod raycast;
+mod resources;
+mod systems;
+
+use bevy::prelude::*;
+
+pub use events::{SnakeClicked, SnakeHovered, SnakeUnhovered};
+pub use raycast::{SnakeRayHit, SnakeRaycast};
+pub use resources::SnakeHover;
+
+pub struct SnakePickingPlugin;
+impl Plugin for SnakePickingPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SnakeHover>()
+            .add_event::<SnakeHovered>()
+            .add_event::<SnakeUnhovered>()
+            .add_event::<SnakeClicked>()
+            .add_systems(
+                PreUpdate,
+                (systems::update_hover, systems::send_clicks)
+                    .chain()
+                    .after(bevy::input::InputSystem),
+            );
+    }
+}
@@ -0,0 +1,71 @@
+use bevy::{ecs::system::SystemParam, prelude::*};
+
+use crate::snake_mesh::{Sdf, SnakeMesh};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SnakeRayHit {
+    pub entity: Entity,
+    // along the ray, in world units
+    pub distance: f32,
+    pub point: Vec3,
+    pub normal: Vec3,
+    // from the first body point to the hit, in the snake's local units
+    pub arc_length: f32,
+}
+
+#[derive(SystemParam)]
+pub struct SnakeRaycast<'w, 's> {
+    snakes: Query<
+        'w,
+        's,
+        (
+            Entity,
+            &'static SnakeMesh,
+            &'static GlobalTransform,
+            &'static InheritedVisibility,
+        ),
+    >,
+}
+
+impl SnakeRaycast<'_, '_> {
+    // closest visible snake hit by the ray
+    pub fn cast_ray(&self, ray: Ray3d, max_distance: f32) -> Option<SnakeRayHit> {
+        let mut closest: Option<SnakeRayHit> = None;
+        for (entity, snake, transform, visibility) in self.snakes.iter() {
+            if !visibility.get() {
+                continue;
+            }
+            let affine = transform.affine();
+            let to_local = affine.inverse();
+            let local_origin = to_local.transform_point3(ray.origin);
+            let local_direction = to_local.transform_vector3(*ray.direction);
+            // local units per world unit along the ray
+            let scale = local_direction.length();
+            if scale <= f32::EPSILON {
+                continue;
+            }
+            let local_direction = local_direction / scale;
+
+            let sdf = snake.sdf();
+            let Some(local_distance) =
+                sdf.raycast(local_origin, local_direction, max_distance * scale)
+            else {
+                continue;
+            };
+            let distance = local_distance / scale;
+            if closest.is_some_and(|hit| hit.distance <= distance) {
+                continue;
+            }
+            let local_point = local_origin + local_direction * local_distance;
+            let normal_matrix = affine.matrix3.inverse().transpose();
+            closest = Some(SnakeRayHit {
+                entity,
+                distance,
+                point: transform.transform_point(local_point),
+                normal: (normal_matrix * sdf.normal(local_point)).normalize_or_zero(),
+                arc_length: sdf.arc_length(local_point),
+            });
+        }
+        closest
+    }
+}
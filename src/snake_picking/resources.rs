@@ -0,0 +1,7 @@
+use bevy::prelude::*;
+
+use super::SnakeRayHit;
+
+// snake under the cursor
+#[derive(Resource, Default, Debug)]
+pub struct SnakeHover(pub Option<SnakeRayHit>);
@@ -0,0 +1,70 @@
+use bevy::{prelude::*, window::PrimaryWindow};
+
+use super::events::{SnakeClicked, SnakeHovered, SnakeUnhovered};
+use super::raycast::SnakeRaycast;
+use super::resources::SnakeHover;
+
+const MAX_PICKING_DISTANCE: f32 = 1000.0;
+
+pub fn update_hover(
+    windows: Query<&Window, With<PrimaryWindow>>,
+    cameras: Query<(&Camera, &GlobalTransform)>,
+    raycast: SnakeRaycast,
+    mut hover: ResMut<SnakeHover>,
+    mut hovered_events: EventWriter<SnakeHovered>,
+    mut unhovered_events: EventWriter<SnakeUnhovered>,
+) {
+    let cursor = windows
+        .get_single()
+        .ok()
+        .and_then(|window| window.cursor_position());
+
+    let hit = cursor.and_then(|cursor| {
+        // the camera rendered last is the one on top
+        let mut cameras: Vec<_> = cameras
+            .iter()
+            .filter(|(camera, _)| camera.is_active)
+            .collect();
+        cameras.sort_by_key(|(camera, _)| std::cmp::Reverse(camera.order));
+        cameras.into_iter().find_map(|(camera, camera_transform)| {
+            // viewport_to_world expects the cursor relative to the viewport, not the window
+            let mut viewport_cursor = cursor;
+            if let Some(viewport) = camera.logical_viewport_rect() {
+                if !viewport.contains(cursor) {
+                    return None;
+                }
+                viewport_cursor -= viewport.min;
+            }
+            let ray = camera.viewport_to_world(camera_transform, viewport_cursor)?;
+            raycast.cast_ray(ray, MAX_PICKING_DISTANCE)
+        })
+    });
+
+    let previous = hover.0.map(|hit| hit.entity);
+    let current = hit.map(|hit| hit.entity);
+    if previous != current {
+        if let Some(previous) = previous {
+            unhovered_events.send(SnakeUnhovered(previous));
+        }
+        if let Some(hit) = hit {
+            hovered_events.send(SnakeHovered(hit));
+        }
+    }
+    hover.0 = hit;
+}
+
+pub fn send_clicks(
+    mouse: Res<ButtonInput<MouseButton>>,
+    hover: Res<SnakeHover>,
+    mut events: EventWriter<SnakeClicked>,
+) {
+    let Some(hit) = hover.0 else {
+        return;
+    };
+    for button in mouse.get_just_pressed() {
+        events.send(SnakeClicked {
+            hit,
+            button: *button,
+        });
+    }
+}